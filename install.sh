@@ -60,6 +60,7 @@ After=network.target
 User=$USER
 Type=simple
 ExecStart=$DESTINATION_DIR/$BINARY_NAME start --rpc-url=http://localhost:9650/ext/bc/C/rpc --block-window 3000 --submit-address= --submit-signature-address= --signing-policy-address=
+ExecReload=/bin/kill -HUP $MAINPID
 Restart=never
 
 [Install]
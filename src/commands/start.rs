@@ -1,91 +1,62 @@
 use colored::Colorize;
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    num::{NonZeroU16, NonZeroU64},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
 };
-use tokio::sync::watch;
 
-use clap::ArgMatches;
-use tracing::{error, info};
+use clap::{parser::ValueSource, ArgMatches};
+use tracing::{error, info, warn};
 use web3::futures::future::join_all;
 
 use crate::{
+    helpers::{flr_to_wei, format_flr, parse_address, redact, redact_url},
     monitor,
     rpc::RpcClient,
     server,
-    types::{Data, MonError, RuntimeConfig},
+    types::{
+        validate_identities, AddressRole, AlertHysteresis, BalanceThresholds, ConfigFile, Data,
+        DutyDeadlines, FlrAmount, Identity, MonError, RewardEpochSchedule, RuntimeConfig,
+        VotingRoundSchedule,
+    },
 };
 
-pub async fn start(args: &ArgMatches) -> Result<(), MonError> {
-    let tg_api_key = args
-        .get_one::<String>("tg-api-key")
-        .map(|s| s.clone())
-        .unwrap();
-    let tg_chat_id = args
-        .get_one::<String>("tg-chat-id")
-        .map(|s| s.clone())
-        .clone()
-        .unwrap();
-    let metrics_port = args.get_one::<u16>("metrics-port").copied().unwrap();
-    let metrics_addr = args.get_one::<String>("metrics-addr").unwrap().to_string();
-    let rpc_url = args.get_one::<String>("rpc-url").unwrap().to_string();
-    let block_window = *args.get_one::<u16>("block-window").unwrap();
-    let submit_address = args
-        .get_one::<String>("submit-address")
-        .unwrap()
-        .to_string();
-    let submit_signature_address = args
-        .get_one::<String>("submit-signature-address")
-        .unwrap()
-        .to_string();
-    let signing_policy_address = args
-        .get_one::<String>("signing-policy-address")
-        .unwrap()
-        .to_string();
+// How long the monitor and the metrics server get to stop on SIGTERM or SIGINT
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
-    info!("===================");
-    info!("Args found: ");
-    info!("--tg-api-key: {:?}", tg_api_key);
-    info!("--tg-chat-id: {:?}", tg_chat_id);
-    info!("--metrics-port: {}", metrics_port);
-    info!("--metrics-addr: {}", metrics_addr);
-    info!("--rpc-url: {}", rpc_url);
-    info!("--block-window: {}", block_window);
-    info!("--submit-address: {}", submit_address);
-    info!("--submit-signature-address: {}", submit_signature_address);
-    info!("--signing-policy-address: {}", signing_policy_address);
-    info!("===================");
+// What the monitor and the metrics server are started with
+struct Settings {
+    metrics_addr: String,
+    metrics_port: u16,
+    config: RuntimeConfig,
+}
 
-    let rpc_client = RpcClient::new(rpc_url.clone())?;
-    let config = RuntimeConfig {
-        // Create the runtime config
-        tg_api_key,
-        tg_chat_id,
-        rpc_client,
-        block_window,
-        submit_address,
-        submit_signature_address,
-        signing_policy_address,
-    };
-    let config_clone = config.clone();
+pub async fn start(args: &ArgMatches) -> Result<(), MonError> {
+    let Settings {
+        metrics_addr,
+        metrics_port,
+        config,
+    } = load_settings(args)?;
+    let (config_sender, config_receiver) = watch::channel(config);
 
     let stop_flag = Arc::new(AtomicBool::new(false));
-    let (s, rx1) = watch::channel::<Data>(Data {
-        signing_policy_tx_found: false,
-        signing_policy_balance: 0.0,
-        submit_tx_found: false,
-        submit_balance: 0.0,
-        submit_signature_tx_found: false,
-        submit_signature_balance: 0.0,
-    });
+    let (s, rx1) = watch::channel::<Data>(Data::default());
 
     let stop_flag_clone = stop_flag.clone();
+    let config_receiver_clone = config_receiver.clone();
 
     // Start the monitoring here
     // And share the monitoring finding with the metrics server via a broadcast channel
     info!("{}", String::from("Starting monitoring thread...").green());
     let monitor_handle = tokio::spawn(async move {
-        match monitor(config_clone, s, stop_flag_clone).await {
+        match monitor(config_receiver_clone, s, stop_flag_clone).await {
             Ok(_) => {
                 let output = String::from("Monitoring server stopped gracefully...").green();
                 info!("{output}");
@@ -101,15 +72,21 @@ pub async fn start(args: &ArgMatches) -> Result<(), MonError> {
         "{}",
         String::from("Starting metrics server thread...").green()
     );
+    let server = server::run(
+        metrics_addr.clone(),
+        metrics_port,
+        SHUTDOWN_TIMEOUT,
+        config_receiver,
+        rx1,
+    )?;
+    let server_control = server.handle();
+    let stop_flag_clone = stop_flag.clone();
     let server_handle = tokio::spawn(async move {
-        match server::run(metrics_addr, metrics_port, rpc_url, config, rx1)
-            .unwrap()
-            .await
-        {
+        match server.await {
             Ok(_) => {
                 let output = String::from("Metrics server stopped gracefully...").green();
                 info!("{output}");
-                stop_flag.store(true, Ordering::Relaxed);
+                stop_flag_clone.store(true, Ordering::Relaxed);
             }
             Err(err) => {
                 let output = format!("Metrics server error: {err:?}").green();
@@ -118,8 +95,497 @@ pub async fn start(args: &ArgMatches) -> Result<(), MonError> {
         }
     });
 
-    // Wait for both the threads to finish
-    _ = join_all([monitor_handle, server_handle]).await;
+    // Swap the config of the monitor and the metrics server on SIGHUP
+    let args = args.clone();
+    tokio::spawn(async move {
+        if let Err(err) = reload_on_sighup(&args, &metrics_addr, metrics_port, config_sender).await
+        {
+            error!(
+                "{}",
+                format!("Can't reload the config on SIGHUP: {err:?}").red()
+            );
+        }
+    });
+
+    // Wait for both the threads to finish, or for a signal to stop them
+    let handles = join_all([monitor_handle, server_handle]);
+    tokio::pin!(handles);
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    tokio::select! {
+        _ = &mut handles => return Ok(()),
+        _ = sigterm.recv() => info!("{}", String::from("Got SIGTERM, shutting down...").green()),
+        _ = sigint.recv() => info!("{}", String::from("Got SIGINT, shutting down...").green()),
+    }
+
+    // The monitor finishes its loop, so the alerts of that loop are still sent,
+    // and the metrics server finishes the requests it's serving
+    stop_flag.store(true, Ordering::Relaxed);
+    let shutdown = async {
+        server_control.stop(true).await;
+        handles.await
+    };
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown)
+        .await
+        .is_err()
+    {
+        let output = format!(
+            "Couldn't stop within {}s, exiting anyway...",
+            SHUTDOWN_TIMEOUT.as_secs()
+        )
+        .red();
+        warn!("{output}");
+    }
 
     Ok(())
 }
+
+// Load the settings again on every SIGHUP, a config which isn't valid is refused
+// and the current one is kept
+async fn reload_on_sighup(
+    args: &ArgMatches,
+    metrics_addr: &str,
+    metrics_port: u16,
+    config_sender: watch::Sender<RuntimeConfig>,
+) -> Result<(), MonError> {
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    while sighup.recv().await.is_some() {
+        info!(
+            "{}",
+            String::from("Got SIGHUP, reloading the config...").green()
+        );
+
+        let settings = match load_settings(args) {
+            Ok(settings) => settings,
+            Err(err) => {
+                error!(
+                    "{}",
+                    format!("Refused the new config, keeping the current one: {err}").red()
+                );
+                continue;
+            }
+        };
+
+        if settings.metrics_addr != metrics_addr || settings.metrics_port != metrics_port {
+            warn!(
+                "{}",
+                format!(
+                    "The metrics server keeps listening on {metrics_addr}:{metrics_port} until restarted"
+                )
+                .yellow()
+            );
+        }
+
+        let changes = config_sender.borrow().changes(&settings.config);
+        if changes.is_empty() {
+            info!("The config didn't change");
+        }
+        for change in &changes {
+            info!("{}", format!("Config changed: {change}").yellow());
+        }
+
+        config_sender.send_replace(settings.config);
+    }
+
+    Ok(())
+}
+
+fn load_settings(args: &ArgMatches) -> Result<Settings, MonError> {
+    let config_path = args.get_one::<String>("config").cloned();
+    let file = config_path
+        .as_deref()
+        .map(ConfigFile::load)
+        .transpose()?
+        .unwrap_or_default();
+
+    let tg_api_key = setting(args, "tg-api-key", file.alerting.tg_api_key.clone()).unwrap();
+    let tg_chat_id = setting(args, "tg-chat-id", file.alerting.tg_chat_id.clone()).unwrap();
+    if tg_api_key.is_empty() != tg_chat_id.is_empty() {
+        return Err(MonError::InvalidArgument(String::from(
+            "--tg-api-key and --tg-chat-id have to be given together, as flags or in the [alerting] section of --config",
+        )));
+    }
+    let metrics_port = setting(args, "metrics-port", file.metrics_port).unwrap();
+    let metrics_addr = setting(args, "metrics-addr", file.metrics_addr.clone()).unwrap();
+    let rpc_urls = settings(args, "rpc-url", file.rpc.url.clone());
+    if rpc_urls.is_empty() {
+        return Err(MonError::InvalidArgument(String::from(
+            "--rpc-url is required, either as a flag or as url in the [rpc] section of --config",
+        )));
+    }
+    let block_window = setting(args, "block-window", file.rpc.block_window).unwrap();
+    let poll_interval = setting(args, "poll-interval", file.rpc.poll_interval).unwrap();
+    if poll_interval.is_zero() {
+        return Err(MonError::InvalidArgument(String::from(
+            "--poll-interval has to be longer than 0s",
+        )));
+    }
+    let ws_url = setting(args, "ws-url", file.rpc.ws_url.clone());
+    if let Some(ws_url) = &ws_url {
+        if !ws_url.starts_with("ws://") && !ws_url.starts_with("wss://") {
+            return Err(MonError::InvalidArgument(format!(
+                "--ws-url has to start with ws:// or wss://, got {}",
+                redact_url(ws_url)
+            )));
+        }
+    }
+    let fetch_concurrency = setting(
+        args,
+        "fetch-concurrency",
+        file.rpc.fetch_concurrency.map(NonZeroU16::get),
+    )
+    .unwrap() as usize;
+    let detection_mode = setting(args, "detection-mode", file.rpc.detection_mode).unwrap();
+    let identity_name = args.get_one::<String>("identity-name").unwrap().to_string();
+    let submit_address = args.get_one::<String>("submit-address").cloned();
+    let submit_signature_address = args.get_one::<String>("submit-signature-address").cloned();
+    let signing_policy_address = args.get_one::<String>("signing-policy-address").cloned();
+    let file_thresholds = &file.alerting.balance_thresholds;
+    let submit_balance = threshold_amounts(
+        args,
+        "submit-balance",
+        file_thresholds.amounts(AddressRole::Submit),
+    );
+    let submit_signature_balance = threshold_amounts(
+        args,
+        "submit-signature-balance",
+        file_thresholds.amounts(AddressRole::SubmitSignature),
+    );
+    let signing_policy_balance = threshold_amounts(
+        args,
+        "signing-policy-balance",
+        file_thresholds.amounts(AddressRole::SigningPolicy),
+    );
+    let submit_balance_thresholds = balance_thresholds("--submit-balance", &submit_balance)?;
+    let submit_signature_balance_thresholds =
+        balance_thresholds("--submit-signature-balance", &submit_signature_balance)?;
+    let signing_policy_balance_thresholds =
+        balance_thresholds("--signing-policy-balance", &signing_policy_balance)?;
+    let burn_rate_window = setting(
+        args,
+        "burn-rate-window",
+        file.alerting.burn_rate_window.map(NonZeroU64::get),
+    )
+    .unwrap();
+    let runway_warning_hours = setting(
+        args,
+        "runway-warning-hours",
+        file.alerting.runway_warning_hours,
+    )
+    .unwrap();
+    let alert_repeat_interval = setting(
+        args,
+        "alert-repeat-interval",
+        file.alerting.repeat_interval.map(NonZeroU64::get),
+    )
+    .unwrap();
+    // The flags override the file condition by condition
+    let mut hysteresis = AlertHysteresis::default();
+    for (condition, condition_hysteresis) in &file.alerting.hysteresis {
+        hysteresis.set(condition, *condition_hysteresis)?;
+    }
+    for setting in args.get_many::<String>("hysteresis").into_iter().flatten() {
+        let (condition, condition_hysteresis) = setting.split_once('=').ok_or_else(|| {
+            MonError::InvalidArgument(format!(
+                "--hysteresis {setting} isn't condition=fire_after[,resolve_after]"
+            ))
+        })?;
+        hysteresis.set(condition, condition_hysteresis.parse()?)?;
+    }
+    let submission_address = setting(args, "submission-address", file.submission_address.clone())
+        .map(|address| parse_address("--submission-address", &address))
+        .transpose()?;
+    let identity_address = args.get_one::<String>("identity-address").cloned();
+    let contract_registry_address = parse_address(
+        "--contract-registry-address",
+        &setting(
+            args,
+            "contract-registry-address",
+            file.contract_registry_address.clone(),
+        )
+        .unwrap(),
+    )?;
+    let first_voting_round_start_ts = setting(
+        args,
+        "first-voting-round-start-ts",
+        file.first_voting_round_start_ts,
+    )
+    .unwrap();
+    let voting_round_duration = setting(
+        args,
+        "voting-round-duration",
+        file.voting_round_duration.map(NonZeroU64::get),
+    )
+    .unwrap();
+    let first_reward_epoch_start_round = setting(
+        args,
+        "first-reward-epoch-start-round",
+        file.first_reward_epoch_start_round,
+    )
+    .unwrap();
+    let reward_epoch_duration_rounds = setting(
+        args,
+        "reward-epoch-duration-rounds",
+        file.reward_epoch_duration_rounds.map(NonZeroU64::get),
+    )
+    .unwrap();
+    let uptime_vote_deadline =
+        setting(args, "uptime-vote-deadline", file.uptime_vote_deadline).unwrap();
+    let rewards_deadline = setting(args, "rewards-deadline", file.rewards_deadline).unwrap();
+    let signing_policy_deadline = setting(
+        args,
+        "signing-policy-deadline",
+        file.signing_policy_deadline,
+    )
+    .unwrap();
+    let duty_deadline_warning =
+        setting(args, "duty-deadline-warning", file.duty_deadline_warning).unwrap();
+
+    // The address flags make one identity and every --identity another one,
+    // all of them share the balance thresholds
+    // The identities come either from the command line or from the [[identities]] of the config file,
+    // whose balance thresholds fall back on the shared ones
+    let mut identities = vec![];
+    match (
+        &submit_address,
+        &submit_signature_address,
+        &signing_policy_address,
+    ) {
+        (Some(submit_address), Some(submit_signature_address), Some(signing_policy_address)) => {
+            identities.push(Identity {
+                name: identity_name.clone(),
+                submit_address: submit_address.clone(),
+                submit_signature_address: submit_signature_address.clone(),
+                signing_policy_address: signing_policy_address.clone(),
+                identity_address: identity_address.clone(),
+                submit_balance_thresholds,
+                submit_signature_balance_thresholds,
+                signing_policy_balance_thresholds,
+            })
+        }
+        (None, None, None) => {}
+        _ => {
+            return Err(MonError::InvalidArgument(String::from(
+                "--submit-address, --submit-signature-address and --signing-policy-address have to be given together",
+            )))
+        }
+    }
+    for identity in args.get_many::<String>("identity").into_iter().flatten() {
+        identities.push(Identity {
+            submit_balance_thresholds,
+            submit_signature_balance_thresholds,
+            signing_policy_balance_thresholds,
+            ..identity.parse::<Identity>()?
+        });
+    }
+    if !identities.is_empty() && !file.identities.is_empty() {
+        return Err(MonError::InvalidArgument(String::from(
+            "Give the identities either with the address flags and --identity or as [[identities]] in --config, not both",
+        )));
+    }
+    for identity in &file.identities {
+        let thresholds = |role: AddressRole, shared: &(Option<String>, Option<String>)| {
+            let (warning, critical) = identity.balance_thresholds.amounts(role);
+            balance_thresholds(
+                &format!(
+                    "identities.{}.balance-thresholds.{}",
+                    identity.name,
+                    role.metric_prefix().replace('_', "-")
+                ),
+                &(
+                    warning.map(FlrAmount::to_string).or(shared.0.clone()),
+                    critical.map(FlrAmount::to_string).or(shared.1.clone()),
+                ),
+            )
+        };
+        identities.push(Identity {
+            name: identity.name.clone(),
+            submit_address: identity.submit_address.clone(),
+            submit_signature_address: identity.submit_signature_address.clone(),
+            signing_policy_address: identity.signing_policy_address.clone(),
+            identity_address: identity.identity_address.clone(),
+            submit_balance_thresholds: thresholds(AddressRole::Submit, &submit_balance)?,
+            submit_signature_balance_thresholds: thresholds(
+                AddressRole::SubmitSignature,
+                &submit_signature_balance,
+            )?,
+            signing_policy_balance_thresholds: thresholds(
+                AddressRole::SigningPolicy,
+                &signing_policy_balance,
+            )?,
+        });
+    }
+    if identities.is_empty() {
+        return Err(MonError::InvalidArgument(String::from(
+            "No identity to monitor, give --submit-address, --submit-signature-address and --signing-policy-address, --identity or [[identities]] in --config",
+        )));
+    }
+    for identity in &mut identities {
+        identity.normalize_addresses()?;
+    }
+    validate_identities(&identities)?;
+
+    info!("===================");
+    info!("Args found: ");
+    info!("--config: {:?}", config_path);
+    info!("--tg-api-key: {}", redact(&tg_api_key));
+    info!("--tg-chat-id: {:?}", tg_chat_id);
+    info!("--metrics-port: {}", metrics_port);
+    info!("--metrics-addr: {}", metrics_addr);
+    info!(
+        "--rpc-url: {:?}",
+        rpc_urls
+            .iter()
+            .map(|url| redact_url(url))
+            .collect::<Vec<_>>()
+    );
+    info!("--block-window: {}", block_window);
+    info!("--poll-interval: {:?}", poll_interval);
+    info!("--ws-url: {:?}", ws_url.as_deref().map(redact_url));
+    info!("--fetch-concurrency: {}", fetch_concurrency);
+    info!("--detection-mode: {}", detection_mode);
+    info!("--identity-name: {}", identity_name);
+    info!("--submit-address: {:?}", submit_address);
+    info!("--submit-signature-address: {:?}", submit_signature_address);
+    info!("--signing-policy-address: {:?}", signing_policy_address);
+    info!("--submit-balance-*: {:?}", submit_balance_thresholds);
+    info!(
+        "--submit-signature-balance-*: {:?}",
+        submit_signature_balance_thresholds
+    );
+    info!(
+        "--signing-policy-balance-*: {:?}",
+        signing_policy_balance_thresholds
+    );
+    info!("--burn-rate-window: {}", burn_rate_window);
+    info!("--runway-warning-hours: {}", runway_warning_hours);
+    info!("--alert-repeat-interval: {}", alert_repeat_interval);
+    info!("--hysteresis: {:?}", hysteresis);
+    info!("--submission-address: {:?}", submission_address);
+    info!("--identity-address: {:?}", identity_address);
+    info!("Identities: {:?}", identities);
+    info!("--contract-registry-address: {}", contract_registry_address);
+    info!(
+        "--first-voting-round-start-ts: {}",
+        first_voting_round_start_ts
+    );
+    info!("--voting-round-duration: {}", voting_round_duration);
+    info!(
+        "--first-reward-epoch-start-round: {}",
+        first_reward_epoch_start_round
+    );
+    info!(
+        "--reward-epoch-duration-rounds: {}",
+        reward_epoch_duration_rounds
+    );
+    info!("--uptime-vote-deadline: {}", uptime_vote_deadline);
+    info!("--rewards-deadline: {}", rewards_deadline);
+    info!("--signing-policy-deadline: {}", signing_policy_deadline);
+    info!("--duty-deadline-warning: {}", duty_deadline_warning);
+    info!("===================");
+
+    let rpc_client = RpcClient::new(rpc_urls, ws_url)?;
+    let config = RuntimeConfig {
+        // Create the runtime config
+        tg_api_key,
+        tg_chat_id,
+        rpc_client,
+        block_window,
+        poll_interval,
+        fetch_concurrency,
+        detection_mode,
+        voting_rounds: VotingRoundSchedule {
+            first_round_start_ts: first_voting_round_start_ts,
+            round_duration: voting_round_duration,
+        },
+        reward_epochs: RewardEpochSchedule {
+            first_epoch_start_round: first_reward_epoch_start_round,
+            epoch_duration_rounds: reward_epoch_duration_rounds,
+        },
+        duty_deadlines: DutyDeadlines {
+            uptime_vote: uptime_vote_deadline,
+            rewards: rewards_deadline,
+            signing_policy: signing_policy_deadline,
+            warning: duty_deadline_warning,
+        },
+        identities,
+        burn_rate_window,
+        runway_warning_hours,
+        alert_repeat_interval,
+        hysteresis,
+        submission_address,
+        contract_registry_address,
+    };
+
+    Ok(Settings {
+        metrics_addr,
+        metrics_port,
+        config,
+    })
+}
+
+// A flag given on the command line wins over the config file, which wins
+// over the default value of the flag
+fn setting<T: Clone + Send + Sync + 'static>(
+    args: &ArgMatches,
+    id: &str,
+    file_value: Option<T>,
+) -> Option<T> {
+    if args.value_source(id) == Some(ValueSource::CommandLine) {
+        return args.get_one::<T>(id).cloned();
+    }
+    file_value.or_else(|| args.get_one::<T>(id).cloned())
+}
+
+// Same as `setting` for the flags which can be repeated, all their values replace the file's
+fn settings(args: &ArgMatches, id: &str, file_values: Option<Vec<String>>) -> Vec<String> {
+    if args.value_source(id) == Some(ValueSource::CommandLine) {
+        return args
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+    }
+    file_values.unwrap_or_default()
+}
+
+// The "<prefix>-warning" and "<prefix>-critical" FLR amounts
+fn threshold_amounts(
+    args: &ArgMatches,
+    prefix: &str,
+    (warning, critical): (Option<&FlrAmount>, Option<&FlrAmount>),
+) -> (Option<String>, Option<String>) {
+    let amount = |level: &str, file_value: Option<&FlrAmount>| {
+        setting(
+            args,
+            &format!("{prefix}-{level}"),
+            file_value.map(FlrAmount::to_string),
+        )
+    };
+    (amount("warning", warning), amount("critical", critical))
+}
+
+// Parse the warning and critical FLR amounts of `name`
+fn balance_thresholds(
+    name: &str,
+    (warning, critical): &(Option<String>, Option<String>),
+) -> Result<BalanceThresholds, MonError> {
+    let thresholds = BalanceThresholds {
+        warning: warning.as_deref().map(flr_to_wei).transpose()?,
+        critical: critical.as_deref().map(flr_to_wei).transpose()?,
+    };
+
+    if let (Some(warning), Some(critical)) = (thresholds.warning, thresholds.critical) {
+        if critical > warning {
+            return Err(MonError::InvalidArgument(format!(
+                "{name}-critical ({}) can't be above {name}-warning ({})",
+                format_flr(critical),
+                format_flr(warning)
+            )));
+        }
+    }
+
+    Ok(thresholds)
+}
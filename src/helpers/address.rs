@@ -0,0 +1,39 @@
+use std::str::FromStr;
+
+use web3::types::H160;
+
+use crate::types::MonError;
+
+// `address` in the lowercase 0x form the addresses of the txs are compared in
+// `name` tells which setting it comes from
+pub fn parse_address(name: &str, address: &str) -> Result<String, MonError> {
+    H160::from_str(address.trim())
+        .map(|address| format!("{address:?}"))
+        .map_err(|_| MonError::InvalidArgument(format!("{name} isn't a valid address: {address}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalises_the_address() {
+        let address = "0x0000000000000000000000000000000000000aBc";
+        assert_eq!(
+            parse_address("--submission-address", address).unwrap(),
+            "0x0000000000000000000000000000000000000abc"
+        );
+        assert_eq!(
+            parse_address("--submission-address", &address[2..]).unwrap(),
+            "0x0000000000000000000000000000000000000abc"
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_addresses() {
+        for address in ["", "0x1", "0x000000000000000000000000000000000000000g"] {
+            let err = parse_address("--submission-address", address).unwrap_err();
+            assert!(err.to_string().contains("--submission-address"), "{err}");
+        }
+    }
+}
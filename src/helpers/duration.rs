@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use crate::types::MonError;
+
+// Parse a duration like "500ms", "180s", "3m", "1h" or "2d", a plain number is in seconds
+pub fn parse_duration(duration: &str) -> Result<Duration, MonError> {
+    let duration = duration.trim();
+    let invalid = || {
+        MonError::InvalidArgument(format!(
+            "{duration} isn't a duration like 500ms, 180s, 3m, 1h or 2d"
+        ))
+    };
+
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (value, unit) = duration.split_at(unit_start);
+    let value = value.parse::<u64>().map_err(|_| invalid())?;
+    let unit_millis = match unit.trim() {
+        "ms" => 1,
+        "" | "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return Err(invalid()),
+    };
+
+    value
+        .checked_mul(unit_millis)
+        .map(Duration::from_millis)
+        .ok_or_else(invalid)
+}
+
+// The inverse of `parse_duration`, in the largest unit which keeps the duration exact
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis % 1_000 != 0 {
+        return format!("{millis}ms");
+    }
+
+    let seconds = millis / 1_000;
+    match [(86_400, "d"), (3_600, "h"), (60, "m")]
+        .into_iter()
+        .find(|(unit_seconds, _)| seconds > 0 && seconds % unit_seconds == 0)
+    {
+        Some((unit_seconds, unit)) => format!("{}{unit}", seconds / unit_seconds),
+        None => format!("{seconds}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration(" 180s ").unwrap(), Duration::from_secs(180));
+        assert_eq!(parse_duration("180").unwrap(), Duration::from_secs(180));
+        assert_eq!(parse_duration("3m").unwrap(), Duration::from_secs(180));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(172_800));
+        assert_eq!(parse_duration("0s").unwrap(), Duration::ZERO);
+        for invalid in ["", "s", "-1s", "1.5s", "3 weeks", "99999999999999999d"] {
+            assert!(parse_duration(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn format() {
+        for (duration, formatted) in [
+            (Duration::from_millis(1_500), "1500ms"),
+            (Duration::from_secs(90), "90s"),
+            (Duration::from_secs(180), "3m"),
+            (Duration::from_secs(7_200), "2h"),
+            (Duration::from_secs(86_400), "1d"),
+            (Duration::ZERO, "0s"),
+        ] {
+            assert_eq!(format_duration(duration), formatted);
+            assert_eq!(parse_duration(formatted).unwrap(), duration);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use web3::types::U256;
+
+use crate::types::MonError;
+
+const FLR_DECIMALS: usize = 18;
+
+fn wei_per_flr() -> U256 {
+    U256::exp10(FLR_DECIMALS)
+}
+
+// Only meant for presenting a balance, e.g. as a metric
+pub fn wei_to_flr(wei: U256) -> f64 {
+    let (whole, fraction) = wei.div_mod(wei_per_flr());
+    let whole = whole.to_string().parse::<f64>().unwrap_or(f64::MAX);
+
+    whole + fraction.as_u64() as f64 / 10f64.powi(FLR_DECIMALS as i32)
+}
+
+// The exact amount of FLR in `wei`, without trailing zeros
+pub fn format_flr(wei: U256) -> String {
+    let (whole, fraction) = wei.div_mod(wei_per_flr());
+    if fraction.is_zero() {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0>width$}", fraction.as_u64(), width = FLR_DECIMALS);
+    format!("{whole}.{}", fraction.trim_end_matches('0'))
+}
+
+// Parse a decimal amount of FLR (e.g. "12.5") into wei
+pub fn flr_to_wei(flr: &str) -> Result<U256, MonError> {
+    let invalid = || MonError::InvalidArgument(format!("{flr} isn't a valid amount of FLR"));
+
+    let (whole, fraction) = flr.trim().split_once('.').unwrap_or((flr.trim(), ""));
+    if whole.is_empty() && fraction.is_empty()
+        || fraction.len() > FLR_DECIMALS
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let whole = match whole {
+        "" => U256::zero(),
+        whole => U256::from_dec_str(whole).map_err(|_| invalid())?,
+    };
+    let fraction = match fraction {
+        "" => U256::zero(),
+        fraction => {
+            U256::from_dec_str(fraction).map_err(|_| invalid())?
+                * U256::exp10(FLR_DECIMALS - fraction.len())
+        }
+    };
+
+    whole
+        .checked_mul(wei_per_flr())
+        .and_then(|wei| wei.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wei(wei: &str) -> U256 {
+        U256::from_dec_str(wei).unwrap()
+    }
+
+    #[test]
+    fn flr_to_wei_is_exact() {
+        assert_eq!(flr_to_wei("1").unwrap(), wei("1000000000000000000"));
+        assert_eq!(flr_to_wei(" 12.5 ").unwrap(), wei("12500000000000000000"));
+        assert_eq!(flr_to_wei(".5").unwrap(), wei("500000000000000000"));
+        assert_eq!(flr_to_wei("3.").unwrap(), wei("3000000000000000000"));
+        assert_eq!(flr_to_wei("0.000000000000000001").unwrap(), wei("1"));
+        // Beyond what an f64 holds exactly
+        assert_eq!(
+            flr_to_wei("123456789.123456789123456789").unwrap(),
+            wei("123456789123456789123456789")
+        );
+
+        for invalid in [
+            "",
+            ".",
+            "-1",
+            "1e3",
+            "1.2.3",
+            "0.0000000000000000001",
+            "1 000",
+        ] {
+            assert!(flr_to_wei(invalid).is_err(), "{invalid}");
+        }
+        // Overflows a U256
+        assert!(flr_to_wei(&"9".repeat(78)).is_err());
+    }
+
+    #[test]
+    fn format_flr_round_trips() {
+        for flr in [
+            "0",
+            "1",
+            "12.5",
+            "0.000000000000000001",
+            "123456789.123456789123456789",
+        ] {
+            assert_eq!(format_flr(flr_to_wei(flr).unwrap()), flr);
+        }
+        assert_eq!(format_flr(wei("1500000000000000000")), "1.5");
+    }
+
+    #[test]
+    fn wei_to_flr_presents_the_amount() {
+        assert_eq!(wei_to_flr(wei("12500000000000000000")), 12.5);
+        assert_eq!(wei_to_flr(U256::zero()), 0.0);
+    }
+}
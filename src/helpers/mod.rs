@@ -1,5 +1,13 @@
-mod send_message;
+mod address;
+mod duration;
 mod escape_for_telegram_markdown_v2;
+mod flr_amount;
+mod redact;
+mod send_message;
 
-pub use send_message::*;
+pub use address::*;
+pub use duration::*;
 pub use escape_for_telegram_markdown_v2::*;
+pub use flr_amount::*;
+pub use redact::*;
+pub use send_message::*;
@@ -1,8 +1,13 @@
 use crate::helpers::escape_for_telegram_markdown_v2;
 use reqwest::Client;
 use serde::Serialize;
+use std::time::Duration;
 use tracing::{debug, error, info};
 
+// A message which Telegram didn't take by then is given up on, so that it doesn't hold up
+// the monitor or its shutdown
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Serialize)]
 struct SendMessageParams {
     chat_id: String,
@@ -17,8 +22,9 @@ pub struct Sender {
 }
 
 impl Sender {
+    // Returns once Telegram took the message, or refused it
     pub async fn send_message(&self, message: String) -> anyhow::Result<()> {
-        if self.token.len() == 0 || self.chat_id.len() == 0 {
+        if self.token.is_empty() || self.chat_id.is_empty() {
             debug!(
                 token = self.token,
                 chat_id = self.chat_id,
@@ -35,22 +41,35 @@ impl Sender {
             parse_mode: "MarkdownV2".to_string(),
         };
 
-        let client = Client::new();
-
-        tokio::spawn(async move {
-            let response = client.post(&url).json(&params).send().await?;
-            let status = response.status();
-            let text = response.text().await?;
+        let client = Client::builder().timeout(SEND_TIMEOUT).build()?;
 
-            if status.is_success() {
-                info!("Sending: {}", message);
-                Ok(())
-            } else {
-                error!("Error sending message: {} | Status: {}", text, status);
-                Err(anyhow::anyhow!(text))
+        // The errors would show the URL, which holds the token
+        let (status, text) = match post(&client, &url, &params).await {
+            Ok(response) => response,
+            Err(err) => {
+                let err = err.without_url();
+                error!("Error sending message: {err}");
+                return Err(err.into());
             }
-        });
+        };
 
-        Ok(())
+        if status.is_success() {
+            info!("Sending: {}", message);
+            Ok(())
+        } else {
+            error!("Error sending message: {} | Status: {}", text, status);
+            Err(anyhow::anyhow!(text))
+        }
     }
 }
+
+// Send the message and read the answer of Telegram
+async fn post(
+    client: &Client,
+    url: &str,
+    params: &SendMessageParams,
+) -> Result<(reqwest::StatusCode, String), reqwest::Error> {
+    let response = client.post(url).json(params).send().await?;
+    let status = response.status();
+    Ok((status, response.text().await?))
+}
@@ -3,8 +3,8 @@ pub mod helpers;
 mod metrics;
 mod monitor;
 pub mod rpc;
-pub mod types;
 pub mod server;
+pub mod types;
 
 pub use metrics::*;
 pub use monitor::*;
@@ -1,5 +1,10 @@
-use clap::{value_parser, Arg, Command};
-use ftsov2mon::commands::start;
+use clap::{value_parser, Arg, ArgAction, Command};
+use ftsov2mon::{
+    commands::start,
+    helpers::parse_duration,
+    rpc::FLARE_CONTRACT_REGISTRY_ADDRESS,
+    types::{BlockWindow, DetectionMode},
+};
 use tracing::error;
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 5)]
@@ -28,13 +33,14 @@ async fn main() -> anyhow::Result<()> {
                 .about("Start monitoring")
                 .arg_required_else_help(false)
                 .args([
+                    Arg::new("config")
+                        .long("config")
+                        .help("A TOML file with the settings, the flags given on the command line take precedence over it"),
                     Arg::new("tg-api-key")
                         .long("tg-api-key")
-                        .requires("tg-chat-id")
                         .default_value(""),
                     Arg::new("tg-chat-id")
                         .long("tg-chat-id")
-                        .requires("tg-api-key")
                         .default_value(""),
                     Arg::new("metrics-port")
                         .long("metrics-port")
@@ -45,28 +51,138 @@ async fn main() -> anyhow::Result<()> {
                         .default_value("0.0.0.0"),
                     Arg::new("rpc-url")
                         .long("rpc-url")
-                        .help("A Flare Network JSON RPC URL")
-                        .required(true),
+                        .action(ArgAction::Append)
+                        .help("A Flare Network JSON RPC URL, required unless it's in --config. Can be repeated for fallback endpoints, in priority order"),
                     Arg::new("block-window")
                         .long("block-window")
-                        .value_parser(value_parser!(u16))
+                        .value_parser(|window: &str| window.parse::<BlockWindow>())
                         .default_value("100")
-                        .help("The number of blocks from now in the past to monitor"),
+                        .help("The number of blocks from now in the past to monitor, or a duration like 180s which is translated to blocks with the block timestamps"),
+                    Arg::new("poll-interval")
+                        .long("poll-interval")
+                        .value_parser(parse_duration)
+                        .default_value("1s")
+                        .help("The minimum time between two checks, like 500ms, 5s or 1m"),
+                    Arg::new("ws-url")
+                        .long("ws-url")
+                        .help("A WebSocket RPC URL to get the new blocks pushed from, checking each of them as soon as it's there instead of polling"),
+                    Arg::new("fetch-concurrency")
+                        .long("fetch-concurrency")
+                        .value_parser(value_parser!(u16).range(1..))
+                        .default_value("10")
+                        .help("The maximum number of blocks fetched from the RPC at the same time"),
+                    Arg::new("detection-mode")
+                        .long("detection-mode")
+                        .value_parser(value_parser!(DetectionMode))
+                        .default_value("blocks")
+                        .help("How to detect txs from the FTSO addresses"),
+                    Arg::new("identity-name")
+                        .long("identity-name")
+                        .default_value("ftso")
+                        .help("The name of the identity given by the address flags, used in the alerts and as the metrics label"),
+                    Arg::new("identity")
+                        .long("identity")
+                        .action(ArgAction::Append)
+                        .help("Another identity to monitor as name=submit,submit_signature,signing_policy[,identity], can be repeated"),
                     Arg::new("submit-address")
                         .long("submit-address")
                         .alias("sa")
-                        .help("The FTSO Submit Address")
-                        .required(true),
+                        .help("The FTSO Submit Address"),
                     Arg::new("submit-signature-address")
                         .long("submit-signature-address")
                         .alias("ssa")
-                        .help("The FTSO Submit Signature Address")
-                        .required(true),
+                        .help("The FTSO Submit Signature Address"),
                     Arg::new("signing-policy-address")
                         .long("signing-policy-address")
                         .alias("spa")
-                        .help("The FTSO Signing Policy Address")
-                        .required(true),
+                        .help("The FTSO Signing Policy Address"),
+                    Arg::new("submit-balance-warning")
+                        .long("submit-balance-warning")
+                        .help("The FLR balance of the submit address under which a warning is sent"),
+                    Arg::new("submit-balance-critical")
+                        .long("submit-balance-critical")
+                        .help("The FLR balance of the submit address under which a critical alert is sent"),
+                    Arg::new("submit-signature-balance-warning")
+                        .long("submit-signature-balance-warning")
+                        .help("The FLR balance of the submit signature address under which a warning is sent"),
+                    Arg::new("submit-signature-balance-critical")
+                        .long("submit-signature-balance-critical")
+                        .help("The FLR balance of the submit signature address under which a critical alert is sent"),
+                    Arg::new("signing-policy-balance-warning")
+                        .long("signing-policy-balance-warning")
+                        .help("The FLR balance of the signing policy address under which a warning is sent"),
+                    Arg::new("signing-policy-balance-critical")
+                        .long("signing-policy-balance-critical")
+                        .help("The FLR balance of the signing policy address under which a critical alert is sent"),
+                    Arg::new("burn-rate-window")
+                        .long("burn-rate-window")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("21600")
+                        .help("Seconds of balance history used to compute the burn rate of each address"),
+                    Arg::new("runway-warning-hours")
+                        .long("runway-warning-hours")
+                        .value_parser(value_parser!(u64))
+                        .default_value("48")
+                        .help("Send an alert when the balance of an address is projected to run out within this many hours"),
+                    Arg::new("alert-repeat-interval")
+                        .long("alert-repeat-interval")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("3600")
+                        .help("Seconds before an alert which keeps firing is sent again"),
+                    Arg::new("hysteresis")
+                        .long("hysteresis")
+                        .action(ArgAction::Append)
+                        .help("How long a condition has to be wrong before alerting and right before resolving, as condition=fire_after[,resolve_after] in voting rounds or a duration like 5m. The conditions are participation (3 rounds by default), balance, runway, reverts, registration, duty and rpc-fallback. Can be repeated"),
+                    Arg::new("submission-address")
+                        .long("submission-address")
+                        .help("The Flare Submission contract address. When set, submit and submit signature txs are only counted if they were sent to it"),
+                    Arg::new("identity-address")
+                        .long("identity-address")
+                        .help("The FTSO Identity Address of the identity given by the address flags, used to check the voter registration"),
+                    Arg::new("contract-registry-address")
+                        .long("contract-registry-address")
+                        .default_value(FLARE_CONTRACT_REGISTRY_ADDRESS)
+                        .help("The FlareContractRegistry address, used to find the Flare system contracts"),
+                    Arg::new("first-voting-round-start-ts")
+                        .long("first-voting-round-start-ts")
+                        .value_parser(value_parser!(u64))
+                        .default_value("1658430000")
+                        .help("The unix timestamp at which the first FTSO voting round started, until it's read from the FlareSystemsManager"),
+                    Arg::new("voting-round-duration")
+                        .long("voting-round-duration")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("90")
+                        .help("The duration of an FTSO voting round in seconds, until it's read from the FlareSystemsManager"),
+                    Arg::new("first-reward-epoch-start-round")
+                        .long("first-reward-epoch-start-round")
+                        .value_parser(value_parser!(u64))
+                        .default_value("0")
+                        .help("The voting round at which the first reward epoch started, until it's read from the FlareSystemsManager"),
+                    Arg::new("reward-epoch-duration-rounds")
+                        .long("reward-epoch-duration-rounds")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("3360")
+                        .help("The duration of a reward epoch in voting rounds, until it's read from the FlareSystemsManager"),
+                    Arg::new("uptime-vote-deadline")
+                        .long("uptime-vote-deadline")
+                        .value_parser(value_parser!(u64))
+                        .default_value("21600")
+                        .help("Seconds after the signing of the uptime vote opens by which it has to be signed"),
+                    Arg::new("rewards-deadline")
+                        .long("rewards-deadline")
+                        .value_parser(value_parser!(u64))
+                        .default_value("86400")
+                        .help("Seconds after the signing of the rewards opens by which they have to be signed"),
+                    Arg::new("signing-policy-deadline")
+                        .long("signing-policy-deadline")
+                        .value_parser(value_parser!(u64))
+                        .default_value("7200")
+                        .help("Seconds after the signing of a new signing policy opens by which it has to be signed"),
+                    Arg::new("duty-deadline-warning")
+                        .long("duty-deadline-warning")
+                        .value_parser(value_parser!(u64))
+                        .default_value("3600")
+                        .help("Seconds before the deadline of a signing policy duty which wasn't performed yet to send a warning"),
                 ]),
         )
         .get_matches();
@@ -79,7 +195,7 @@ async fn main() -> anyhow::Result<()> {
     match res {
         Ok(_) => {}
         Err(err) => {
-            error!("Error: {err:?}");
+            error!("Error: {err}");
         }
     }
 
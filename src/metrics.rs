@@ -1,70 +1,429 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
 use anyhow::Context;
-use prometheus::{Encoder, Gauge, IntGauge, Registry, TextEncoder};
+use prometheus::{
+    Encoder, Gauge, GaugeVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
 use tracing::{debug, error};
 
-use crate::{rpc::RpcClient, types::MonError};
+use crate::{
+    helpers::wei_to_flr,
+    rpc::RpcClient,
+    types::{IdentityData, MonError, SigningDuty, SubmissionFunction},
+};
 
+// The metrics of each identity have an "identity" label with its name
 #[derive(Debug, Clone)]
 pub struct Metrics {
-    pub signing_policy_tx_found: IntGauge,
-    pub signing_policy_balance: Gauge,
+    pub signing_policy_tx_found: IntGaugeVec,
+    pub signing_policy_tx_successful: IntCounterVec,
+    pub signing_policy_tx_reverted: IntCounterVec,
+    pub signing_policy_balance: GaugeVec,
+    pub signing_policy_balance_burn_rate: GaugeVec,
+    pub signing_policy_hours_until_empty: GaugeVec,
+    pub signing_policy_gas_cost: GaugeVec,
+    pub signing_policy_gas_cost_voting_round: GaugeVec,
+    pub signing_policy_gas_cost_reward_epoch: GaugeVec,
 
-    pub submit_tx_found: IntGauge,
-    pub submit_balance: Gauge,
+    pub submit_tx_found: IntGaugeVec,
+    pub submit_tx_successful: IntCounterVec,
+    pub submit_tx_reverted: IntCounterVec,
+    pub submit_balance: GaugeVec,
+    pub submit_balance_burn_rate: GaugeVec,
+    pub submit_hours_until_empty: GaugeVec,
+    pub submit_gas_cost: GaugeVec,
+    pub submit_gas_cost_voting_round: GaugeVec,
+    pub submit_gas_cost_reward_epoch: GaugeVec,
 
-    pub submit_signature_tx_found: IntGauge,
-    pub submit_signature_balance: Gauge,
+    pub submit_signature_tx_found: IntGaugeVec,
+    pub submit_signature_tx_successful: IntCounterVec,
+    pub submit_signature_tx_reverted: IntCounterVec,
+    pub submit_signature_balance: GaugeVec,
+    pub submit_signature_balance_burn_rate: GaugeVec,
+    pub submit_signature_hours_until_empty: GaugeVec,
+    pub submit_signature_gas_cost: GaugeVec,
+    pub submit_signature_gas_cost_voting_round: GaugeVec,
+    pub submit_signature_gas_cost_reward_epoch: GaugeVec,
 
-    pub registered_for_this_epoch: IntGauge,
+    pub registered_for_this_epoch: IntGaugeVec,
+    pub registered_for_next_epoch: IntGaugeVec,
+    pub reward_epoch_id: IntGauge,
     pub is_syncing: IntGauge,
     pub rpc_current_block: Gauge,
+    pub rpc_active_endpoint: IntGauge,
+    pub scan_duration: Gauge,
+    pub monitor_errors: IntCounter,
+    pub monitor_healthy: IntGauge,
+    pub reorgs: IntCounter,
+
+    pub voting_round_id: IntGauge,
+    pub submit_missed_rounds: IntGaugeVec,
+    pub submit_signature_missed_rounds: IntGaugeVec,
+
+    pub submission_calls: IntGaugeVec,
+    pub submission_voting_round: IntGaugeVec,
+
+    pub signing_duty_due: IntGaugeVec,
+    pub signing_duty_seconds_until_deadline: IntGaugeVec,
+    pub signing_duty_last_reward_epoch: IntGaugeVec,
+    // The identities which have metrics, so that the ones removed by a reload can be dropped
+    identities: Arc<Mutex<BTreeSet<String>>>,
     registry: Registry,
 }
 
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Metrics {
     pub fn new() -> Metrics {
         Metrics {
-            signing_policy_tx_found: IntGauge::new(
-                "ftso_signing_policy_tx_found",
-                "Was a tx from the signing policy address found within the block window?",
+            signing_policy_tx_found: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_tx_found",
+                    "Was a tx from the signing policy address found within the block window?",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_tx_successful: IntCounterVec::new(
+                Opts::new(
+                    "ftso_signing_policy_tx_successful_total",
+                    "The successful txs from the signing policy address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_tx_reverted: IntCounterVec::new(
+                Opts::new(
+                    "ftso_signing_policy_tx_reverted_total",
+                    "The reverted txs from the signing policy address",
+                ),
+                &["identity"],
             )
             .unwrap(),
-            signing_policy_balance: Gauge::new(
-                "ftso_signing_policy_balance",
-                "The balance of the signing policy address",
+            signing_policy_balance: GaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_balance",
+                    "The balance of the signing policy address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_balance_burn_rate: GaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_balance_burn_rate",
+                    "The FLR burned per hour by the signing policy address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_hours_until_empty: GaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_hours_until_empty",
+                    "The hours until the balance of the signing policy address runs out at its burn rate",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_gas_cost: GaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_gas_cost",
+                    "The FLR spent on gas by the signing policy address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_gas_cost_voting_round: GaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_gas_cost_voting_round",
+                    "The FLR spent on gas by the signing policy address in the last voting round",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            signing_policy_gas_cost_reward_epoch: GaugeVec::new(
+                Opts::new(
+                    "ftso_signing_policy_gas_cost_reward_epoch",
+                    "The FLR spent on gas by the signing policy address in the current reward epoch",
+                ),
+                &["identity"],
             )
             .unwrap(),
 
-            submit_tx_found: IntGauge::new(
-                "ftso_submit_tx_found",
-                "Was a tx from the submit address found within the block window?",
+            submit_tx_found: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_submit_tx_found",
+                    "Was a tx from the submit address found within the block window?",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_tx_successful: IntCounterVec::new(
+                Opts::new(
+                    "ftso_submit_tx_successful_total",
+                    "The successful txs from the submit address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_tx_reverted: IntCounterVec::new(
+                Opts::new(
+                    "ftso_submit_tx_reverted_total",
+                    "The reverted txs from the submit address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_balance: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_balance",
+                    "The balance of the submit address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_balance_burn_rate: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_balance_burn_rate",
+                    "The FLR burned per hour by the submit address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_hours_until_empty: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_hours_until_empty",
+                    "The hours until the balance of the submit address runs out at its burn rate",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_gas_cost: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_gas_cost",
+                    "The FLR spent on gas by the submit address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_gas_cost_voting_round: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_gas_cost_voting_round",
+                    "The FLR spent on gas by the submit address in the last voting round",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_gas_cost_reward_epoch: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_gas_cost_reward_epoch",
+                    "The FLR spent on gas by the submit address in the current reward epoch",
+                ),
+                &["identity"],
             )
             .unwrap(),
-            submit_balance: Gauge::new("ftso_submit_balance", "The balance of the submit address")
-                .unwrap(),
 
-            submit_signature_tx_found: IntGauge::new(
-                "ftso_submit_signature_tx_found",
-                "Was a tx from the submit signature address found within the block window?",
+            submit_signature_tx_found: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_tx_found",
+                    "Was a tx from the submit signature address found within the block window?",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_tx_successful: IntCounterVec::new(
+                Opts::new(
+                    "ftso_submit_signature_tx_successful_total",
+                    "The successful txs from the submit signature address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_tx_reverted: IntCounterVec::new(
+                Opts::new(
+                    "ftso_submit_signature_tx_reverted_total",
+                    "The reverted txs from the submit signature address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_balance: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_balance",
+                    "The balance of the submit signature address",
+                ),
+                &["identity"],
             )
             .unwrap(),
-            submit_signature_balance: Gauge::new(
-                "ftso_submit_signature_balance",
-                "The balance of the submit signature address",
+            submit_signature_balance_burn_rate: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_balance_burn_rate",
+                    "The FLR burned per hour by the submit signature address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_hours_until_empty: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_hours_until_empty",
+                    "The hours until the balance of the submit signature address runs out at its burn rate",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_gas_cost: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_gas_cost",
+                    "The FLR spent on gas by the submit signature address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_gas_cost_voting_round: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_gas_cost_voting_round",
+                    "The FLR spent on gas by the submit signature address in the last voting round",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_gas_cost_reward_epoch: GaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_gas_cost_reward_epoch",
+                    "The FLR spent on gas by the submit signature address in the current reward epoch",
+                ),
+                &["identity"],
             )
             .unwrap(),
 
-            registered_for_this_epoch: IntGauge::new(
-                "ftso_registered_for_this_epoch",
-                "Did the client register for this reward epoch?",
+            registered_for_this_epoch: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_registered_for_this_epoch",
+                    "Did the client register for this reward epoch?",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            registered_for_next_epoch: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_registered_for_next_epoch",
+                    "Did the client register for the next reward epoch?",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            reward_epoch_id: IntGauge::new(
+                "ftso_reward_epoch_id",
+                "The current reward epoch",
+            )
+            .unwrap(),
+            is_syncing: IntGauge::new(
+                "ftso_rpc_is_syncing",
+                "Is the RPC syncing?",
             )
             .unwrap(),
-            is_syncing: IntGauge::new("ftso_rpc_is_syncing", "Is the RPC syncing?").unwrap(),
             rpc_current_block: Gauge::new(
                 "ftso_rpc_current_block",
                 "The latest block from the RPC",
             )
             .unwrap(),
+            rpc_active_endpoint: IntGauge::new(
+                "ftso_rpc_active_endpoint",
+                "The RPC endpoint the requests go to, in the order of --rpc-url: 1 is the preferred one",
+            )
+            .unwrap(),
+            scan_duration: Gauge::new(
+                "ftso_scan_duration_seconds",
+                "How long the last monitoring cycle took to fetch and scan the new blocks",
+            )
+            .unwrap(),
+            monitor_errors: IntCounter::new(
+                "ftso_monitor_errors_total",
+                "The monitoring cycles which failed, mostly because of RPC errors",
+            )
+            .unwrap(),
+            monitor_healthy: IntGauge::new(
+                "ftso_monitor_healthy",
+                "Did the last monitoring cycle succeed? The other metrics are stale while it's 0",
+            )
+            .unwrap(),
+            reorgs: IntCounter::new(
+                "ftso_reorgs_total",
+                "The chain reorganisations found in the scanned blocks, which were rolled back and scanned again",
+            )
+            .unwrap(),
+
+            voting_round_id: IntGauge::new(
+                "ftso_voting_round_id",
+                "The last voting round checked within the block window",
+            )
+            .unwrap(),
+            submit_missed_rounds: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_submit_missed_rounds",
+                    "The voting rounds within the block window without both a commit and a reveal from the submit address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+            submit_signature_missed_rounds: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_submit_signature_missed_rounds",
+                    "The voting rounds within the block window without submitSignatures from the submit signature address",
+                ),
+                &["identity"],
+            )
+            .unwrap(),
+
+            submission_calls: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_submission_calls",
+                    "The Submission contract calls made by the submit and submit signature addresses",
+                ),
+                &["identity", "function"],
+            )
+            .unwrap(),
+            submission_voting_round: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_submission_voting_round_id",
+                    "The latest voting round ID found in the payloads of each Submission function",
+                ),
+                &["identity", "function"],
+            )
+            .unwrap(),
+
+            signing_duty_due: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_signing_duty_due",
+                    "Is this FlareSystemsManager signature due from the signing policy address and not sent yet?",
+                ),
+                &["identity", "duty"],
+            )
+            .unwrap(),
+            signing_duty_seconds_until_deadline: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_signing_duty_seconds_until_deadline",
+                    "Seconds left to send a due FlareSystemsManager signature, negative once missed",
+                ),
+                &["identity", "duty"],
+            )
+            .unwrap(),
+            signing_duty_last_reward_epoch: IntGaugeVec::new(
+                Opts::new(
+                    "ftso_signing_duty_last_reward_epoch",
+                    "The last reward epoch the signing policy address sent this FlareSystemsManager signature for",
+                ),
+                &["identity", "duty"],
+            )
+            .unwrap(),
+            identities: Arc::default(),
             registry: Registry::new(),
         }
     }
@@ -73,39 +432,187 @@ impl Metrics {
         self.registry
             .register(Box::new(self.signing_policy_tx_found.clone()))
             .context("Couldn't register signing_policy_tx_found")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_tx_successful.clone()))
+            .context("Couldn't register signing_policy_tx_successful")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_tx_reverted.clone()))
+            .context("Couldn't register signing_policy_tx_reverted")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.signing_policy_balance.clone()))
             .context("Couldn't register signing_policy_balance")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_balance_burn_rate.clone()))
+            .context("Couldn't register signing_policy_balance_burn_rate")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_hours_until_empty.clone()))
+            .context("Couldn't register signing_policy_hours_until_empty")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_gas_cost.clone()))
+            .context("Couldn't register signing_policy_gas_cost")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_gas_cost_voting_round.clone()))
+            .context("Couldn't register signing_policy_gas_cost_voting_round")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_policy_gas_cost_reward_epoch.clone()))
+            .context("Couldn't register signing_policy_gas_cost_reward_epoch")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.submit_tx_found.clone()))
             .context("Couldn't register submit_tx_found")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_tx_successful.clone()))
+            .context("Couldn't register submit_tx_successful")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_tx_reverted.clone()))
+            .context("Couldn't register submit_tx_reverted")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.submit_balance.clone()))
             .context("Couldn't register submit_balance")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_balance_burn_rate.clone()))
+            .context("Couldn't register submit_balance_burn_rate")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_hours_until_empty.clone()))
+            .context("Couldn't register submit_hours_until_empty")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_gas_cost.clone()))
+            .context("Couldn't register submit_gas_cost")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_gas_cost_voting_round.clone()))
+            .context("Couldn't register submit_gas_cost_voting_round")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_gas_cost_reward_epoch.clone()))
+            .context("Couldn't register submit_gas_cost_reward_epoch")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.submit_signature_tx_found.clone()))
             .context("Couldn't register submit_signature_tx_found")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_signature_tx_successful.clone()))
+            .context("Couldn't register submit_signature_tx_successful")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_signature_tx_reverted.clone()))
+            .context("Couldn't register submit_signature_tx_reverted")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.submit_signature_balance.clone()))
             .context("Couldn't register submit_signature_balance")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_signature_balance_burn_rate.clone()))
+            .context("Couldn't register submit_signature_balance_burn_rate")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_signature_hours_until_empty.clone()))
+            .context("Couldn't register submit_signature_hours_until_empty")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_signature_gas_cost.clone()))
+            .context("Couldn't register submit_signature_gas_cost")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(
+                self.submit_signature_gas_cost_voting_round.clone(),
+            ))
+            .context("Couldn't register submit_signature_gas_cost_voting_round")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(
+                self.submit_signature_gas_cost_reward_epoch.clone(),
+            ))
+            .context("Couldn't register submit_signature_gas_cost_reward_epoch")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.registered_for_this_epoch.clone()))
             .context("Couldn't register registered_for_this_epoch")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.registered_for_next_epoch.clone()))
+            .context("Couldn't register registered_for_next_epoch")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.reward_epoch_id.clone()))
+            .context("Couldn't register reward_epoch_id")
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.is_syncing.clone()))
             .context("Couldn't register is_syncing")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
         self.registry
             .register(Box::new(self.rpc_current_block.clone()))
             .context("Couldn't register rpc_current_block")
-            .map_err(|e| MonError::RegisterError(e.into()))?;
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.rpc_active_endpoint.clone()))
+            .context("Couldn't register rpc_active_endpoint")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.scan_duration.clone()))
+            .context("Couldn't register scan_duration")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.monitor_errors.clone()))
+            .context("Couldn't register monitor_errors")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.monitor_healthy.clone()))
+            .context("Couldn't register monitor_healthy")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.reorgs.clone()))
+            .context("Couldn't register reorgs")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.voting_round_id.clone()))
+            .context("Couldn't register voting_round_id")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_missed_rounds.clone()))
+            .context("Couldn't register submit_missed_rounds")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submit_signature_missed_rounds.clone()))
+            .context("Couldn't register submit_signature_missed_rounds")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submission_calls.clone()))
+            .context("Couldn't register submission_calls")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.submission_voting_round.clone()))
+            .context("Couldn't register submission_voting_round")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_duty_due.clone()))
+            .context("Couldn't register signing_duty_due")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_duty_seconds_until_deadline.clone()))
+            .context("Couldn't register signing_duty_seconds_until_deadline")
+            .map_err(MonError::RegisterError)?;
+        self.registry
+            .register(Box::new(self.signing_duty_last_reward_epoch.clone()))
+            .context("Couldn't register signing_duty_last_reward_epoch")
+            .map_err(MonError::RegisterError)?;
         Ok(())
     }
 
@@ -117,7 +624,7 @@ impl Metrics {
         encoder
             .encode(&metric_families, &mut buffer)
             .context("Couldn't encode metric families")
-            .map_err(|e| MonError::EncodeError(e.into()))?;
+            .map_err(MonError::EncodeError)?;
 
         Ok((encoder, buffer))
     }
@@ -147,6 +654,9 @@ impl Metrics {
             }
         };
 
+        self.rpc_active_endpoint
+            .set(rpc_client.active_endpoint() as i64 + 1);
+
         // Set the latest block from the RPC
         let current_block = rpc_client.current_block().await?;
         self.rpc_current_block.set(current_block as f64);
@@ -158,28 +668,347 @@ impl Metrics {
         &self,
         data: &crate::types::Data,
     ) -> Result<(), MonError> {
-        self.signing_policy_balance.set(data.signing_policy_balance);
-        self.submit_balance.set(data.submit_balance);
+        self.scan_duration.set(data.scan_duration);
+        self.monitor_errors.inc_by(
+            data.monitor_errors
+                .saturating_sub(self.monitor_errors.get()),
+        );
+        self.monitor_healthy.set(data.monitor_healthy as i64);
+        self.reorgs
+            .inc_by(data.reorgs.saturating_sub(self.reorgs.get()));
+        self.voting_round_id.set(data.voting_round_id as i64);
+        self.reward_epoch_id.set(data.reward_epoch_id as i64);
+
+        for identity in &data.identities {
+            self.update_for_identity(identity, data.block_timestamp);
+        }
+
+        let names: BTreeSet<String> = data
+            .identities
+            .iter()
+            .map(|identity| identity.name.clone())
+            .collect();
+        let mut identities = self
+            .identities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for removed in identities.difference(&names) {
+            self.remove_identity(removed);
+        }
+        *identities = names;
+        Ok(())
+    }
+
+    // Drop all the metrics of `identity`, so that an identity removed from the config
+    // stops being exported instead of keeping its last values
+    fn remove_identity(&self, identity: &str) {
+        for counter in [
+            &self.signing_policy_tx_successful,
+            &self.signing_policy_tx_reverted,
+            &self.submit_tx_successful,
+            &self.submit_tx_reverted,
+            &self.submit_signature_tx_successful,
+            &self.submit_signature_tx_reverted,
+        ] {
+            _ = counter.remove_label_values(&[identity]);
+        }
+        for gauge in [
+            &self.signing_policy_tx_found,
+            &self.submit_tx_found,
+            &self.submit_signature_tx_found,
+            &self.registered_for_this_epoch,
+            &self.registered_for_next_epoch,
+            &self.submit_missed_rounds,
+            &self.submit_signature_missed_rounds,
+        ] {
+            _ = gauge.remove_label_values(&[identity]);
+        }
+        for gauge in [
+            &self.signing_policy_balance,
+            &self.signing_policy_balance_burn_rate,
+            &self.signing_policy_hours_until_empty,
+            &self.signing_policy_gas_cost,
+            &self.signing_policy_gas_cost_voting_round,
+            &self.signing_policy_gas_cost_reward_epoch,
+            &self.submit_balance,
+            &self.submit_balance_burn_rate,
+            &self.submit_hours_until_empty,
+            &self.submit_gas_cost,
+            &self.submit_gas_cost_voting_round,
+            &self.submit_gas_cost_reward_epoch,
+            &self.submit_signature_balance,
+            &self.submit_signature_balance_burn_rate,
+            &self.submit_signature_hours_until_empty,
+            &self.submit_signature_gas_cost,
+            &self.submit_signature_gas_cost_voting_round,
+            &self.submit_signature_gas_cost_reward_epoch,
+        ] {
+            _ = gauge.remove_label_values(&[identity]);
+        }
+        for function in SubmissionFunction::ALL {
+            _ = self
+                .submission_calls
+                .remove_label_values(&[identity, function.name()]);
+            _ = self
+                .submission_voting_round
+                .remove_label_values(&[identity, function.name()]);
+        }
+        for duty in SigningDuty::ALL {
+            for gauge in [
+                &self.signing_duty_due,
+                &self.signing_duty_seconds_until_deadline,
+                &self.signing_duty_last_reward_epoch,
+            ] {
+                _ = gauge.remove_label_values(&[identity, duty.name()]);
+            }
+        }
+    }
+
+    fn update_for_identity(&self, data: &IdentityData, block_timestamp: u64) {
+        let identity = data.name.as_str();
+
+        self.signing_policy_balance
+            .with_label_values(&[identity])
+            .set(wei_to_flr(data.signing_policy_balance));
+        self.submit_balance
+            .with_label_values(&[identity])
+            .set(wei_to_flr(data.submit_balance));
         self.submit_signature_balance
-            .set(data.submit_signature_balance);
+            .with_label_values(&[identity])
+            .set(wei_to_flr(data.submit_signature_balance));
+        // No burn means the balance never runs out
+        for (burn_rate, hours_until_empty, runway) in [
+            (
+                &self.signing_policy_balance_burn_rate,
+                &self.signing_policy_hours_until_empty,
+                &data.signing_policy_runway,
+            ),
+            (
+                &self.submit_balance_burn_rate,
+                &self.submit_hours_until_empty,
+                &data.submit_runway,
+            ),
+            (
+                &self.submit_signature_balance_burn_rate,
+                &self.submit_signature_hours_until_empty,
+                &data.submit_signature_runway,
+            ),
+        ] {
+            burn_rate
+                .with_label_values(&[identity])
+                .set(runway.burn_rate.unwrap_or_default());
+            hours_until_empty
+                .with_label_values(&[identity])
+                .set(runway.hours_until_empty.unwrap_or(f64::INFINITY));
+        }
+        for (total, voting_round, reward_epoch, gas_cost) in [
+            (
+                &self.signing_policy_gas_cost,
+                &self.signing_policy_gas_cost_voting_round,
+                &self.signing_policy_gas_cost_reward_epoch,
+                &data.signing_policy_gas_cost,
+            ),
+            (
+                &self.submit_gas_cost,
+                &self.submit_gas_cost_voting_round,
+                &self.submit_gas_cost_reward_epoch,
+                &data.submit_gas_cost,
+            ),
+            (
+                &self.submit_signature_gas_cost,
+                &self.submit_signature_gas_cost_voting_round,
+                &self.submit_signature_gas_cost_reward_epoch,
+                &data.submit_signature_gas_cost,
+            ),
+        ] {
+            total
+                .with_label_values(&[identity])
+                .set(wei_to_flr(gas_cost.total));
+            voting_round
+                .with_label_values(&[identity])
+                .set(wei_to_flr(gas_cost.last_voting_round));
+            reward_epoch
+                .with_label_values(&[identity])
+                .set(wei_to_flr(gas_cost.reward_epoch));
+        }
+
+        self.submit_missed_rounds
+            .with_label_values(&[identity])
+            .set(data.submit_missed_rounds as i64);
+        self.submit_signature_missed_rounds
+            .with_label_values(&[identity])
+            .set(data.submit_signature_missed_rounds as i64);
+
+        // Only the duties which are still pending get a deadline
+        for duty in SigningDuty::ALL {
+            let pending = data
+                .signing_duties
+                .iter()
+                .filter(|status| status.duty == duty && !status.done);
+
+            let deadline = pending.map(|status| status.deadline).min();
+            self.signing_duty_due
+                .with_label_values(&[identity, duty.name()])
+                .set(deadline.is_some() as i64);
+            match deadline {
+                Some(deadline) => self
+                    .signing_duty_seconds_until_deadline
+                    .with_label_values(&[identity, duty.name()])
+                    .set(deadline as i64 - block_timestamp as i64),
+                None => {
+                    _ = self
+                        .signing_duty_seconds_until_deadline
+                        .remove_label_values(&[identity, duty.name()]);
+                }
+            }
+
+            if let Some(reward_epoch_id) = data.signing_duty_last_reward_epochs.get(&duty) {
+                self.signing_duty_last_reward_epoch
+                    .with_label_values(&[identity, duty.name()])
+                    .set(*reward_epoch_id as i64);
+            }
+        }
 
-        if data.submit_signature_tx_found {
-            self.submit_signature_tx_found.set(1);
-        } else {
-            self.submit_signature_tx_found.set(0);
+        // Without an identity address the registration isn't known
+        for (gauge, registered) in [
+            (
+                &self.registered_for_this_epoch,
+                data.registered_for_this_epoch,
+            ),
+            (
+                &self.registered_for_next_epoch,
+                data.registered_for_next_epoch,
+            ),
+        ] {
+            match registered {
+                Some(registered) => gauge.with_label_values(&[identity]).set(registered as i64),
+                None => _ = gauge.remove_label_values(&[identity]),
+            }
         }
 
-        if data.signing_policy_tx_found {
-            self.signing_policy_tx_found.set(1);
-        } else {
-            self.signing_policy_tx_found.set(0);
+        for function in SubmissionFunction::ALL {
+            let calls = data
+                .submission_stats
+                .calls
+                .get(&function)
+                .copied()
+                .unwrap_or_default();
+            self.submission_calls
+                .with_label_values(&[identity, function.name()])
+                .set(calls as i64);
+
+            match data.submission_stats.last_voting_round(function) {
+                Some(voting_round_id) => self
+                    .submission_voting_round
+                    .with_label_values(&[identity, function.name()])
+                    .set(voting_round_id as i64),
+                None => {
+                    _ = self
+                        .submission_voting_round
+                        .remove_label_values(&[identity, function.name()]);
+                }
+            }
         }
 
-        if data.submit_tx_found {
-            self.submit_tx_found.set(1);
-        } else {
-            self.submit_tx_found.set(0);
+        for (tx_found, found) in [
+            (&self.signing_policy_tx_found, data.signing_policy_tx_found),
+            (&self.submit_tx_found, data.submit_tx_found),
+            (
+                &self.submit_signature_tx_found,
+                data.submit_signature_tx_found,
+            ),
+        ] {
+            tx_found.with_label_values(&[identity]).set(found as i64);
         }
-        Ok(())
+
+        // A reorg can take back txs, the counters then wait until the totals are above them again
+        for (counter, total) in [
+            (
+                &self.signing_policy_tx_successful,
+                data.signing_policy_tx_successful,
+            ),
+            (
+                &self.signing_policy_tx_reverted,
+                data.signing_policy_tx_reverted,
+            ),
+            (&self.submit_tx_successful, data.submit_tx_successful),
+            (&self.submit_tx_reverted, data.submit_tx_reverted),
+            (
+                &self.submit_signature_tx_successful,
+                data.submit_signature_tx_successful,
+            ),
+            (
+                &self.submit_signature_tx_reverted,
+                data.submit_signature_tx_reverted,
+            ),
+        ] {
+            let counter = counter.with_label_values(&[identity]);
+            counter.inc_by(total.saturating_sub(counter.get()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::core::Collector;
+
+    use crate::types::Data;
+
+    fn data(names: &[&str]) -> Data {
+        Data {
+            identities: names
+                .iter()
+                .map(|name| IdentityData {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn removed_identities_are_dropped() {
+        let metrics = Metrics::new();
+        metrics.register().unwrap();
+        metrics
+            .update_for_monitoring_data(&data(&["a", "b"]))
+            .await
+            .unwrap();
+        assert_eq!(metrics.submit_balance.collect()[0].get_metric().len(), 2);
+
+        metrics
+            .update_for_monitoring_data(&data(&["b"]))
+            .await
+            .unwrap();
+        for family in metrics.registry.gather() {
+            for metric in family.get_metric() {
+                assert!(
+                    metric
+                        .get_label()
+                        .iter()
+                        .all(|label| label.get_value() != "a"),
+                    "{}",
+                    family.get_name()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn registration_is_only_exported_when_known() {
+        let metrics = Metrics::new();
+        metrics.register().unwrap();
+        let mut data = data(&["a", "b"]);
+        data.identities[0].registered_for_this_epoch = Some(true);
+        data.identities[0].registered_for_next_epoch = Some(false);
+        metrics.update_for_monitoring_data(&data).await.unwrap();
+
+        let registered = metrics.registered_for_next_epoch.collect();
+        let registered = registered[0].get_metric();
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].get_label()[0].get_value(), "a");
+        assert_eq!(registered[0].get_gauge().get_value(), 0.0);
     }
 }
@@ -0,0 +1,272 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::types::{AddressRole, Hysteresis, SigningDuty};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Warning,
+    Critical,
+}
+
+// What an alert is about, the identity is None for the conditions of the monitor itself
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct AlertKey {
+    pub identity: Option<String>,
+    pub condition: AlertCondition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum AlertCondition {
+    Monitor,
+    RpcFallback,
+    Participation(AddressRole),
+    Balance(AddressRole),
+    Runway(AddressRole),
+    // Txs reverted within the window
+    Reverts(AddressRole),
+    // Not registered for the reward epoch
+    Registration(u64),
+    // The duty of the reward epoch wasn't performed
+    Duty(SigningDuty, u64),
+}
+
+impl AlertKey {
+    pub fn monitor(condition: AlertCondition) -> AlertKey {
+        AlertKey {
+            identity: None,
+            condition,
+        }
+    }
+
+    pub fn identity(identity: &str, condition: AlertCondition) -> AlertKey {
+        AlertKey {
+            identity: Some(identity.to_string()),
+            condition,
+        }
+    }
+}
+
+// A condition which is wrong in this loop
+// `key` identifies the condition across loops and `recovery` is sent once it clears,
+// a condition without a recovery message is cleared silently
+#[derive(Debug, Clone)]
+pub(crate) struct Alert {
+    pub key: AlertKey,
+    pub severity: Severity,
+    pub message: String,
+    pub recovery: Option<String>,
+    pub hysteresis: Hysteresis,
+}
+
+#[derive(Debug, Clone)]
+struct Firing {
+    severity: Severity,
+    recovery: Option<String>,
+    last_sent: Instant,
+    resolve_after: Duration,
+    // Since when the condition is right
+    passing_since: Option<Instant>,
+}
+
+// The firing alerts, so that each of them is only sent when it starts firing or changes severity,
+// repeated every `repeat_interval` while it keeps firing and followed by its recovery message
+#[derive(Debug, Clone)]
+pub(crate) struct Alerts {
+    repeat_interval: Duration,
+    firing: HashMap<AlertKey, Firing>,
+    // Since when the conditions which aren't firing yet are wrong
+    failing: HashMap<AlertKey, Instant>,
+}
+
+impl Alerts {
+    pub fn new(repeat_interval: Duration) -> Alerts {
+        Alerts {
+            repeat_interval,
+            firing: HashMap::new(),
+            failing: HashMap::new(),
+        }
+    }
+
+    pub fn set_repeat_interval(&mut self, repeat_interval: Duration) {
+        self.repeat_interval = repeat_interval;
+    }
+
+    // Forget the alerts for which `keep` is false, without sending their recovery
+    pub fn retain(&mut self, keep: impl Fn(&AlertKey) -> bool) {
+        self.firing.retain(|key, _| keep(key));
+        self.failing.retain(|key, _| keep(key));
+    }
+
+    pub fn is_firing(&self, key: &AlertKey) -> bool {
+        self.firing.contains_key(key)
+    }
+
+    // Take all the alerts which are wrong at `now` and return the messages which have to be sent
+    // The hysteresis in voting rounds is counted with `round_duration` seconds per round
+    pub fn update(&mut self, alerts: Vec<Alert>, round_duration: u64, now: Instant) -> Vec<String> {
+        let mut messages = vec![];
+
+        // The alerts which aren't there anymore are resolved once they stayed away long enough
+        let keys: HashSet<&AlertKey> = alerts.iter().map(|alert| &alert.key).collect();
+        self.failing.retain(|key, _| keys.contains(key));
+        self.firing.retain(|key, firing| {
+            if keys.contains(key) {
+                firing.passing_since = None;
+                return true;
+            }
+            let passing_since = *firing.passing_since.get_or_insert(now);
+            if now.saturating_duration_since(passing_since) < firing.resolve_after {
+                return true;
+            }
+            messages.extend(firing.recovery.take());
+            false
+        });
+
+        for alert in alerts {
+            match self.firing.get_mut(&alert.key) {
+                Some(firing)
+                    if firing.severity == alert.severity
+                        && now.saturating_duration_since(firing.last_sent)
+                            < self.repeat_interval =>
+                {
+                    firing.recovery = alert.recovery;
+                }
+                Some(firing) => {
+                    messages.push(alert.message);
+                    firing.severity = alert.severity;
+                    firing.recovery = alert.recovery;
+                    firing.last_sent = now;
+                }
+                None => {
+                    let failing_since = *self.failing.entry(alert.key.clone()).or_insert(now);
+                    let fire_after = alert.hysteresis.fire_after.duration(round_duration);
+                    if now.saturating_duration_since(failing_since) < fire_after {
+                        continue;
+                    }
+
+                    self.failing.remove(&alert.key);
+                    messages.push(alert.message);
+                    self.firing.insert(
+                        alert.key,
+                        Firing {
+                            severity: alert.severity,
+                            recovery: alert.recovery,
+                            last_sent: now,
+                            resolve_after: alert.hysteresis.resolve_after.duration(round_duration),
+                            passing_since: None,
+                        },
+                    );
+                }
+            }
+        }
+
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AlertDelay;
+
+    const ROUND: u64 = 90;
+
+    fn alert(severity: Severity, hysteresis: Hysteresis) -> Alert {
+        Alert {
+            key: AlertKey::identity("a", AlertCondition::Balance(AddressRole::Submit)),
+            severity,
+            message: format!("{severity:?}"),
+            recovery: Some(String::from("resolved")),
+            hysteresis,
+        }
+    }
+
+    #[test]
+    fn fires_once_and_resolves() {
+        let mut alerts = Alerts::new(Duration::from_secs(3600));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let warning = alert(Severity::Warning, Hysteresis::default());
+        assert_eq!(
+            alerts.update(vec![warning.clone()], ROUND, at(0)),
+            ["Warning"]
+        );
+        assert!(alerts.is_firing(&warning.key));
+        assert!(alerts
+            .update(vec![warning.clone()], ROUND, at(2))
+            .is_empty());
+
+        // A change of severity and the repeat interval send it again
+        let critical = alert(Severity::Critical, Hysteresis::default());
+        assert_eq!(
+            alerts.update(vec![critical.clone()], ROUND, at(4)),
+            ["Critical"]
+        );
+        assert!(alerts
+            .update(vec![critical.clone()], ROUND, at(3603))
+            .is_empty());
+        assert_eq!(alerts.update(vec![critical], ROUND, at(3604)), ["Critical"]);
+
+        assert_eq!(alerts.update(vec![], ROUND, at(3606)), ["resolved"]);
+        assert!(!alerts.is_firing(&warning.key));
+        assert!(alerts.update(vec![], ROUND, at(3608)).is_empty());
+    }
+
+    #[test]
+    fn hysteresis_is_counted_in_time() {
+        let mut alerts = Alerts::new(Duration::from_secs(3600));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let hysteresis = Hysteresis {
+            fire_after: AlertDelay::Rounds(2),
+            resolve_after: AlertDelay::Duration(Duration::from_secs(60)),
+        };
+        let warning = alert(Severity::Warning, hysteresis);
+
+        // A loop runs every block, so many loops don't make it fire before 2 rounds
+        for secs in (0..180).step_by(2) {
+            assert!(alerts
+                .update(vec![warning.clone()], ROUND, at(secs))
+                .is_empty());
+        }
+        assert_eq!(
+            alerts.update(vec![warning.clone()], ROUND, at(180)),
+            ["Warning"]
+        );
+
+        // A blip in the middle restarts the time it has to be right
+        assert!(alerts.update(vec![], ROUND, at(182)).is_empty());
+        assert!(alerts
+            .update(vec![warning.clone()], ROUND, at(200))
+            .is_empty());
+        assert!(alerts.update(vec![], ROUND, at(202)).is_empty());
+        assert!(alerts.update(vec![], ROUND, at(261)).is_empty());
+        assert_eq!(alerts.update(vec![], ROUND, at(262)), ["resolved"]);
+
+        // The same goes for the time it has to be wrong
+        assert!(alerts
+            .update(vec![warning.clone()], ROUND, at(300))
+            .is_empty());
+        assert!(alerts.update(vec![], ROUND, at(302)).is_empty());
+        assert!(alerts
+            .update(vec![warning.clone()], ROUND, at(304))
+            .is_empty());
+        assert!(alerts.update(vec![warning], ROUND, at(483)).is_empty());
+    }
+
+    #[test]
+    fn retain_forgets_without_recovery() {
+        let mut alerts = Alerts::new(Duration::from_secs(3600));
+        let now = Instant::now();
+        let warning = alert(Severity::Warning, Hysteresis::default());
+        alerts.update(vec![warning.clone()], ROUND, now);
+
+        alerts.retain(|key| key.identity.as_deref() != Some("a"));
+        assert!(!alerts.is_firing(&warning.key));
+        assert!(alerts.update(vec![], ROUND, now).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+use web3::types::U256;
+
+use super::alerts::{Alert, AlertCondition, AlertKey, Severity};
+use crate::{
+    helpers::format_flr,
+    types::{AddressRole, BalanceLevel, BalanceThresholds, Hysteresis, Runway},
+};
+
+// Firing while the balance of the `role` address of `identity` is below one of its thresholds
+pub(crate) fn balance_alert(
+    identity: &str,
+    role: AddressRole,
+    balance: U256,
+    thresholds: &BalanceThresholds,
+    hysteresis: Hysteresis,
+) -> Option<Alert> {
+    let level = thresholds.level(balance);
+    let severity = match level {
+        BalanceLevel::Ok => return None,
+        BalanceLevel::Warning => Severity::Warning,
+        BalanceLevel::Critical => Severity::Critical,
+    };
+    let threshold = thresholds.threshold(level).unwrap_or_default();
+
+    Some(Alert {
+        key: AlertKey::identity(identity, AlertCondition::Balance(role)),
+        severity,
+        message: format!(
+            "v2: {identity}: {role} balance is {} FLR, below the {level} threshold of {} FLR!",
+            format_flr(balance),
+            format_flr(threshold)
+        ),
+        recovery: Some(format!(
+            "v2: {identity}: {role} balance was topped up, resolved!"
+        )),
+        hysteresis,
+    })
+}
+
+// Firing while the balance of the `role` address of `identity` is projected to run out
+// within `warning_hours`
+pub(crate) fn runway_alert(
+    identity: &str,
+    role: AddressRole,
+    runway: &Runway,
+    warning_hours: u64,
+    hysteresis: Hysteresis,
+) -> Option<Alert> {
+    let (Some(hours_until_empty), Some(burn_rate)) = (runway.hours_until_empty, runway.burn_rate)
+    else {
+        return None;
+    };
+    if !runway.below(warning_hours) {
+        return None;
+    }
+
+    Some(Alert {
+        key: AlertKey::identity(identity, AlertCondition::Runway(role)),
+        severity: Severity::Warning,
+        message: format!(
+            "v2: {identity}: {role} is burning {burn_rate:.2} FLR per hour and will run out in {hours_until_empty:.1} hours!"
+        ),
+        recovery: Some(format!(
+            "v2: {identity}: {role} has more than {warning_hours} hours of runway again, resolved!"
+        )),
+        hysteresis,
+    })
+}
@@ -0,0 +1,64 @@
+use super::{check_epoch::EpochInfo, state::IdentityState};
+use crate::types::{DutyStatus, Identity, RuntimeConfig, SigningDuty};
+
+// The signing duties of the signing policy address which are due at `now` (a block timestamp)
+// A duty is due once its signing opened on chain, and has to be performed within
+// its deadline from then
+// Only the duties which became due after `tracking_since` are checked, as the monitor
+// can't know about the calls made before it started
+// A duty is done once the monitor saw the call, or the FlareSystemsManager says it was signed
+// `unscanned` are the timestamps between which the monitor skipped blocks
+pub(crate) fn check_duties(
+    config: &RuntimeConfig,
+    identity: &Identity,
+    identity_state: &IdentityState,
+    epoch: &EpochInfo,
+    now: u64,
+    tracking_since: u64,
+    unscanned: &[(u64, u64)],
+) -> Vec<DutyStatus> {
+    let deadlines = &config.duty_deadlines;
+
+    // Nothing has to be signed for a reward epoch we didn't register for
+    let registration = identity_state
+        .last_registration_check
+        .map(|_| &identity_state.registration)
+        .filter(|registration| registration.reward_epoch_id == epoch.reward_epoch_id);
+    let registered = |reward_epoch_id: u64| {
+        registration.map_or(true, |registration| {
+            if reward_epoch_id == registration.reward_epoch_id {
+                registration.registered_for_this_epoch
+            } else {
+                registration.registered_for_next_epoch
+            }
+        })
+    };
+    let signed = |duty: SigningDuty, reward_epoch_id: u64| {
+        identity.identity_address.as_ref().is_some_and(|voter| {
+            epoch
+                .signed_duties
+                .contains(&(voter.clone(), duty, reward_epoch_id))
+        })
+    };
+
+    epoch
+        .opened_duties
+        .iter()
+        .filter(|(duty, reward_epoch_id, _)| {
+            *duty != SigningDuty::NewSigningPolicy || registered(*reward_epoch_id)
+        })
+        .filter(|(_, _, opened_at)| now >= *opened_at && *opened_at >= tracking_since)
+        .map(|(duty, reward_epoch_id, opened_at)| DutyStatus {
+            duty: *duty,
+            reward_epoch_id: *reward_epoch_id,
+            deadline: opened_at + deadlines.deadline(*duty),
+            done: identity_state
+                .signing_duties
+                .is_done(*reward_epoch_id, *duty)
+                || signed(*duty, *reward_epoch_id),
+            unscanned: unscanned.iter().any(|(skipped_since, skipped_until)| {
+                *skipped_until >= *opened_at && *skipped_since <= now
+            }),
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeSet;
+
+use tracing::debug;
+
+use crate::types::{
+    MonError, RewardEpochSchedule, RuntimeConfig, SigningDuty, VotingRoundSchedule,
+};
+
+// What the FlareSystemsManager says about the voting rounds and the reward epochs
+#[derive(Debug, Clone)]
+pub(crate) struct EpochInfo {
+    pub voting_rounds: VotingRoundSchedule,
+    pub reward_epochs: RewardEpochSchedule,
+    pub reward_epoch_id: u64,
+    // The signing duties whose signing opened, with the reward epoch they're for
+    // and the timestamp they opened at
+    pub opened_duties: Vec<(SigningDuty, u64, u64)>,
+    // The opened duties the FlareSystemsManager saw signed, with the identity address of the voter
+    pub signed_duties: BTreeSet<(String, SigningDuty, u64)>,
+}
+
+// Read the schedules, the current reward epoch and which signing duties opened from the chain
+// In reward epoch N the uptime vote and the rewards of N - 1 get signed,
+// and the signing policy of N + 1 once it's initialized
+// For the identities with an identity address, the FlareSystemsManager also tells
+// which of them were signed, whether or not the monitor saw the tx
+pub(crate) async fn check_epoch(config: &RuntimeConfig) -> Result<EpochInfo, MonError> {
+    let rpc_client = &config.rpc_client;
+    let flare_systems_manager = rpc_client
+        .contract_address(&config.contract_registry_address, "FlareSystemsManager")
+        .await?;
+
+    let reward_epoch_id = rpc_client
+        .current_reward_epoch_id(&flare_systems_manager)
+        .await?;
+    let mut duties = vec![
+        (SigningDuty::NewSigningPolicy, reward_epoch_id),
+        (SigningDuty::NewSigningPolicy, reward_epoch_id + 1),
+    ];
+    if let Some(previous_epoch_id) = reward_epoch_id.checked_sub(1) {
+        duties.push((SigningDuty::UptimeVote, previous_epoch_id));
+        duties.push((SigningDuty::Rewards, previous_epoch_id));
+    }
+
+    let mut opened_duties = vec![];
+    for (duty, duty_epoch_id) in duties {
+        if let Some(opened_at) = rpc_client
+            .duty_sign_start(&flare_systems_manager, duty, duty_epoch_id)
+            .await?
+        {
+            opened_duties.push((duty, duty_epoch_id, opened_at));
+        }
+    }
+
+    let mut signed_duties = BTreeSet::new();
+    for voter in config
+        .identities
+        .iter()
+        .filter_map(|identity| identity.identity_address.as_ref())
+    {
+        for (duty, duty_epoch_id, _) in &opened_duties {
+            // The txs seen by the monitor still count when this can't be read
+            match rpc_client
+                .voter_duty_sign_ts(&flare_systems_manager, *duty, *duty_epoch_id, voter)
+                .await
+            {
+                Ok(Some(_)) => {
+                    signed_duties.insert((voter.clone(), *duty, *duty_epoch_id));
+                }
+                Ok(None) => {}
+                Err(err) => debug!("Couldn't read whether {voter} signed {duty}: {err:?}"),
+            }
+        }
+    }
+
+    Ok(EpochInfo {
+        voting_rounds: rpc_client
+            .voting_round_schedule(&flare_systems_manager)
+            .await?,
+        reward_epochs: rpc_client
+            .reward_epoch_schedule(&flare_systems_manager)
+            .await?,
+        reward_epoch_id,
+        opened_duties,
+        signed_duties,
+    })
+}
@@ -0,0 +1,55 @@
+use tracing::debug;
+
+use crate::types::{MonError, RuntimeConfig};
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Registration {
+    pub reward_epoch_id: u64,
+    pub registered_for_this_epoch: bool,
+    pub registered_for_next_epoch: bool,
+    // Voters can currently register for the next reward epoch
+    pub next_epoch_registration_open: bool,
+}
+
+// Ask the VoterRegistry whether `identity_address` is registered for the current
+// and the next reward epoch
+pub(crate) async fn check_registration(
+    config: &RuntimeConfig,
+    identity_address: &str,
+) -> Result<Registration, MonError> {
+    let rpc_client = &config.rpc_client;
+    let flare_systems_manager = rpc_client
+        .contract_address(&config.contract_registry_address, "FlareSystemsManager")
+        .await?;
+    let voter_registry = rpc_client
+        .contract_address(&config.contract_registry_address, "VoterRegistry")
+        .await?;
+
+    let reward_epoch_id = rpc_client
+        .current_reward_epoch_id(&flare_systems_manager)
+        .await?;
+    let next_epoch_registration_open = match rpc_client
+        .voter_registration_data(&flare_systems_manager, reward_epoch_id + 1)
+        .await
+    {
+        Ok((_, enabled)) => enabled,
+        Err(err) => {
+            debug!(
+                "No registration data for reward epoch {} yet: {err:?}",
+                reward_epoch_id + 1
+            );
+            false
+        }
+    };
+
+    Ok(Registration {
+        reward_epoch_id,
+        registered_for_this_epoch: rpc_client
+            .is_voter_registered(&voter_registry, identity_address, reward_epoch_id)
+            .await?,
+        registered_for_next_epoch: rpc_client
+            .is_voter_registered(&voter_registry, identity_address, reward_epoch_id + 1)
+            .await?,
+        next_epoch_registration_open,
+    })
+}
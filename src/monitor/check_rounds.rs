@@ -0,0 +1,73 @@
+use std::{collections::HashSet, ops::Range};
+
+use crate::types::{Identity, RuntimeConfig, SightingsWindow, SubmissionFunction};
+
+// The submit address sends submit1 (commit) and submit2 (reveal of the previous round) every round
+const SUBMIT_FUNCTIONS: [SubmissionFunction; 2] =
+    [SubmissionFunction::Submit1, SubmissionFunction::Submit2];
+// The submit signature address sends submitSignatures for the previous round every round
+const SUBMIT_SIGNATURE_FUNCTIONS: [SubmissionFunction; 1] = [SubmissionFunction::SubmitSignatures];
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RoundParticipation {
+    // The voting rounds fully covered by the window
+    pub rounds: Range<u64>,
+    pub submit_missed: Vec<u64>,
+    pub submit_signature_missed: Vec<u64>,
+}
+
+// The voting rounds fully covered by the window, the same for every identity
+pub(crate) fn covered_rounds(config: &RuntimeConfig, window: &SightingsWindow) -> Range<u64> {
+    match (window.first_timestamp(), window.last_timestamp()) {
+        (Some(first_ts), Some(last_ts)) => config.voting_rounds.complete_rounds(first_ts, last_ts),
+        _ => 0..0,
+    }
+}
+
+// Check every voting round fully covered by the window for the txs `identity` has to send
+pub(crate) fn check_rounds(
+    config: &RuntimeConfig,
+    identity: &Identity,
+    window: &SightingsWindow,
+) -> RoundParticipation {
+    let schedule = config.voting_rounds;
+    let submit_address = identity.submit_address.to_lowercase();
+    let submit_signature_address = identity.submit_signature_address.to_lowercase();
+
+    let mut participation = RoundParticipation {
+        rounds: covered_rounds(config, window),
+        ..Default::default()
+    };
+
+    for round_id in participation.rounds.clone() {
+        let (mut submit_calls, mut submit_signature_calls) = (HashSet::new(), HashSet::new());
+        for sighting in
+            window.sightings_between(schedule.round_start(round_id), schedule.round_end(round_id))
+        {
+            let Some(call) = sighting.call.as_ref().filter(|_| !sighting.reverted) else {
+                continue;
+            };
+            if sighting.from == submit_address {
+                submit_calls.insert(call.function);
+            }
+            if sighting.from == submit_signature_address {
+                submit_signature_calls.insert(call.function);
+            }
+        }
+
+        if !SUBMIT_FUNCTIONS
+            .iter()
+            .all(|function| submit_calls.contains(function))
+        {
+            participation.submit_missed.push(round_id);
+        }
+        if !SUBMIT_SIGNATURE_FUNCTIONS
+            .iter()
+            .all(|function| submit_signature_calls.contains(function))
+        {
+            participation.submit_signature_missed.push(round_id);
+        }
+    }
+
+    participation
+}
@@ -0,0 +1,14 @@
+mod alerts;
+mod check_balances;
+mod check_duties;
+mod check_epoch;
+mod check_registration;
+mod check_rounds;
+mod new_heads;
+mod nonce_activity;
+mod run;
+mod scan_blocks;
+mod state;
+mod trace_activity;
+
+pub use run::*;
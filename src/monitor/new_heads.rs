@@ -0,0 +1,136 @@
+use colored::Colorize;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use web3::{
+    api::SubscriptionStream,
+    futures::{FutureExt, StreamExt},
+    transports::WebSocket,
+    types::BlockHeader,
+};
+
+use tracing::{info, warn};
+
+use super::run::{sleep_unless_stopped, STOP_CHECK_INTERVAL};
+use crate::{helpers::redact_url, rpc::RpcClient};
+
+// How long to poll before subscribing again after the subscription dropped
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(30);
+// A subscription which didn't push any block for this long is considered dropped
+const HEAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+// The newHeads subscription on the WebSocket URL of the RPC client, if there's one
+// The monitor polls while there's no subscription
+#[derive(Default)]
+pub(crate) struct NewHeads {
+    // The WebSocket URL it was made on, to subscribe again when it's reloaded
+    subscription: Option<(String, SubscriptionStream<WebSocket, BlockHeader>)>,
+    last_attempt: Option<Instant>,
+}
+
+impl NewHeads {
+    // Wait for the next block pushed over the WebSocket and return its header
+    // Without a subscription, sleep for `poll_wait` and return None
+    // Returns None right away when the subscription drops, so that the blocks are polled again
+    pub async fn wait(
+        &mut self,
+        rpc_client: &RpcClient,
+        poll_wait: Option<Duration>,
+        stop_flag: &AtomicBool,
+    ) -> Option<BlockHeader> {
+        self.subscribe(rpc_client).await;
+
+        let Some((ws_url, subscription)) = &mut self.subscription else {
+            if let Some(poll_wait) = poll_wait {
+                sleep_unless_stopped(poll_wait, stop_flag).await;
+            }
+            return None;
+        };
+
+        match next_head(subscription, stop_flag).await {
+            Ok(head) => head,
+            Err(reason) => {
+                let output = format!(
+                    "The newHeads subscription on {} dropped because {reason}! Polling until it's back...",
+                    redact_url(ws_url)
+                )
+                .red();
+                warn!("{output}");
+                self.subscription = None;
+                None
+            }
+        }
+    }
+
+    // Subscribe if there's a WebSocket URL and no subscription on it yet,
+    // at most once every RESUBSCRIBE_INTERVAL
+    async fn subscribe(&mut self, rpc_client: &RpcClient) {
+        let Some(ws_url) = &rpc_client.ws_url else {
+            self.subscription = None;
+            return;
+        };
+        match &self.subscription {
+            Some((subscribed_url, _)) if subscribed_url == ws_url => return,
+            // The URL was reloaded
+            Some(_) => {
+                self.subscription = None;
+                self.last_attempt = None;
+            }
+            None => {}
+        }
+        if self
+            .last_attempt
+            .is_some_and(|last_attempt| last_attempt.elapsed() < RESUBSCRIBE_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_attempt = Some(Instant::now());
+        match rpc_client.subscribe_new_heads().await {
+            Ok(subscription) => {
+                let output = format!("Subscribed to newHeads on {}", redact_url(ws_url)).green();
+                info!("{output}");
+                self.subscription = Some((ws_url.clone(), subscription));
+            }
+            Err(err) => {
+                let output = format!(
+                    "Couldn't subscribe to newHeads on {}: {err}! Polling meanwhile...",
+                    redact_url(ws_url)
+                )
+                .red();
+                warn!("{output}");
+            }
+        }
+    }
+}
+
+// The header of the next block pushed by `subscription`, None if the monitor is stopping
+// Returns why the subscription dropped otherwise
+async fn next_head(
+    subscription: &mut SubscriptionStream<WebSocket, BlockHeader>,
+    stop_flag: &AtomicBool,
+) -> Result<Option<BlockHeader>, String> {
+    let waiting_since = Instant::now();
+    loop {
+        if stop_flag.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        match tokio::time::timeout(STOP_CHECK_INTERVAL, subscription.next()).await {
+            Err(_) if waiting_since.elapsed() < HEAD_TIMEOUT => continue,
+            Err(_) => {
+                return Err(format!("no block came for {}s", HEAD_TIMEOUT.as_secs()));
+            }
+            Ok(Some(Ok(mut head))) => {
+                // Only the latest of the blocks which piled up during the last loop matters
+                while let Some(Some(Ok(next))) = subscription.next().now_or_never() {
+                    head = next;
+                }
+                return Ok(Some(head));
+            }
+            Ok(Some(Err(err))) => return Err(err.to_string()),
+            Ok(None) => return Err(String::from("the connection was closed")),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use tracing::debug;
+
+use crate::types::{MonError, RuntimeConfig};
+
+// Find which of the `tracked_addresses` sent a tx within `block_range` by
+// comparing their nonce right before the range with their nonce at its last block
+// This needs the RPC to still have the state at the start of the range
+// `nonces` keeps the nonce at the end of the range, which the next range usually starts right after
+pub(crate) async fn nonce_activity(
+    config: &RuntimeConfig,
+    tracked_addresses: &[String],
+    nonces: &mut HashMap<String, (u64, u64)>,
+    block_range: Range<u64>,
+) -> Result<HashSet<String>, MonError> {
+    let mut active = HashSet::new();
+    if block_range.is_empty() {
+        return Ok(active);
+    }
+
+    for address in tracked_addresses {
+        let nonce_before = match block_range.start.checked_sub(1) {
+            Some(block_id) => match nonces.get(address) {
+                Some(&(nonce_block, nonce)) if nonce_block == block_id => nonce,
+                _ => {
+                    config
+                        .rpc_client
+                        .get_transaction_count(address.clone(), block_id)
+                        .await?
+                }
+            },
+            None => 0,
+        };
+        let nonce_after = config
+            .rpc_client
+            .get_transaction_count(address.clone(), block_range.end - 1)
+            .await?;
+        nonces.insert(address.clone(), (block_range.end - 1, nonce_after));
+
+        debug!("Nonce of {address}: {nonce_before} -> {nonce_after}");
+        if nonce_after > nonce_before {
+            active.insert(address.clone());
+        }
+    }
+
+    Ok(active)
+}
@@ -0,0 +1,839 @@
+use colored::Colorize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+use tracing::{debug, error, info, warn};
+
+use super::{
+    alerts::{Alert, AlertCondition, AlertKey, Alerts, Severity},
+    check_balances::{balance_alert, runway_alert},
+    check_duties::check_duties,
+    check_epoch::check_epoch,
+    check_registration::check_registration,
+    check_rounds::{check_rounds, covered_rounds},
+    new_heads::NewHeads,
+    nonce_activity::nonce_activity,
+    scan_blocks::{scan_blocks, NewBlocks},
+    state::MonitorState,
+    trace_activity::trace_activity,
+};
+use crate::{
+    helpers::{format_duration, format_flr, Sender},
+    types::{
+        AddressRole, BlockWindow, Data, DetectionMode, GasCostSummary, Hysteresis, IdentityData,
+        MonError, RuntimeConfig, SightingsWindow,
+    },
+};
+
+// How often a sleeping monitor checks whether it has to stop
+pub(super) const STOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// The voter registration only changes a few times per reward epoch
+const REGISTRATION_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+// The signing phases open a few times per reward epoch, and their deadlines count from
+// the timestamp read from the chain
+const EPOCH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// The burn rate of a balance sampled over less time than this is mostly noise
+const MIN_BURN_RATE_SPAN: u64 = 600;
+// A failed loop is retried after waiting twice as long as after the previous failure
+const RETRY_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+// A single RPC hiccup isn't worth an alert
+const MONITOR_FAILURES_BEFORE_ALERT: u32 = 3;
+// How often the RPC endpoints preferred over the active one are checked to switch back to them
+const ENDPOINT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+// A head which didn't move for this many voting rounds means the RPC is stuck
+const STALLED_HEAD_ROUNDS: u64 = 3;
+
+// Whether a loop had a new block to check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckOutcome {
+    Checked,
+    // The chain didn't move since the last loop, which is neither a failure nor a recovery
+    // until it stalled for STALLED_HEAD_ROUNDS
+    NoNewBlock,
+}
+
+// What one loop of the monitor works with and keeps for the next one
+struct MonitorContext {
+    config: RuntimeConfig,
+    sender: Sender,
+    state: MonitorState,
+    alerts: Alerts,
+    tracked_addresses: Vec<String>,
+    monitoring_sender: watch::Sender<Data>,
+    stop_flag: Arc<AtomicBool>,
+}
+
+pub async fn monitor(
+    mut config_receiver: watch::Receiver<RuntimeConfig>,
+    monitoring_sender: watch::Sender<Data>,
+    stop_flag: Arc<AtomicBool>,
+) -> Result<(), MonError> {
+    let config = config_receiver.borrow_and_update().clone();
+    let mut monitor_alerts = Alerts::new(Duration::from_secs(config.alert_repeat_interval));
+    let mut ctx = MonitorContext {
+        sender: Sender {
+            token: config.tg_api_key.clone(),
+            chat_id: config.tg_chat_id.clone(),
+        },
+        state: MonitorState::new(&config.identities),
+        alerts: Alerts::new(Duration::from_secs(config.alert_repeat_interval)),
+        tracked_addresses: config.tracked_addresses(),
+        config,
+        monitoring_sender,
+        stop_flag,
+    };
+    let mut failures = 0;
+    // Kept as it is by the loops without a new block
+    let mut failure: Option<Alert> = None;
+    let mut last_endpoint_check: Option<Instant> = None;
+    let mut new_heads = NewHeads::default();
+    // The block pushed over the WebSocket, the next loop checks up to it
+    let mut head = None;
+
+    loop {
+        if ctx.stop_flag.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        // A reloaded config is only swapped in between two loops
+        if config_receiver.has_changed().unwrap_or(false) {
+            let new_config = config_receiver.borrow_and_update().clone();
+            info!("{}", String::from("Using the reloaded config").green());
+
+            monitor_alerts
+                .set_repeat_interval(Duration::from_secs(new_config.alert_repeat_interval));
+            ctx.reload(new_config);
+        }
+
+        if last_endpoint_check.map_or(true, |checked_at| {
+            checked_at.elapsed() >= ENDPOINT_CHECK_INTERVAL
+        }) {
+            ctx.config.rpc_client.check_endpoints().await;
+            last_endpoint_check = Some(Instant::now());
+        }
+
+        // A failed loop is retried after a backoff, with what was learned so far
+        let loop_start = Instant::now();
+        let backoff = match ctx.check(head.take()).await {
+            Ok(CheckOutcome::Checked) => {
+                if failures > 0 {
+                    let output = format!("The monitor recovered after {failures} failed loops");
+                    info!("{}", output.green());
+                }
+                failures = 0;
+                failure = None;
+                None
+            }
+            Ok(CheckOutcome::NoNewBlock) => None,
+            Err(err) => {
+                failures += 1;
+                ctx.state.errors += 1;
+                let backoff = retry_backoff(failures);
+                let output = format!(
+                    "Monitoring loop failed {failures} times in a row: {err}, retrying in {}s...",
+                    backoff.as_secs()
+                )
+                .red();
+                error!("{output}");
+
+                // The data of the last good loop is kept
+                let errors = ctx.state.errors;
+                ctx.monitoring_sender.send_modify(|data| {
+                    data.monitor_errors = errors;
+                    data.monitor_healthy = false;
+                });
+
+                // A failure or two is retried without alerting
+                failure = (failures >= MONITOR_FAILURES_BEFORE_ALERT).then(|| Alert {
+                    key: AlertKey::monitor(AlertCondition::Monitor),
+                    severity: Severity::Critical,
+                    message: format!(
+                        "v2: The monitor failed {failures} times in a row, the last time with: {err}!"
+                    ),
+                    recovery: Some(String::from("v2: The monitor is working again, resolved!")),
+                    hysteresis: Hysteresis::default(),
+                });
+                Some(backoff)
+            }
+        };
+
+        // The URLs aren't in the message, they often hold an API key
+        let active_endpoint = ctx.config.rpc_client.active_endpoint();
+        let fallback = (active_endpoint > 0).then(|| Alert {
+            key: AlertKey::monitor(AlertCondition::RpcFallback),
+            severity: Severity::Warning,
+            message: format!(
+                "v2: The preferred RPC endpoint is down or behind, running on the fallback endpoint {} of {}!",
+                active_endpoint + 1,
+                ctx.config.rpc_client.endpoint_count()
+            ),
+            recovery: Some(String::from(
+                "v2: Back on the preferred RPC endpoint, resolved!",
+            )),
+            hysteresis: ctx.config.hysteresis.rpc_fallback,
+        });
+
+        // The alerts of the monitor itself are kept apart from the alerts of the identities
+        for message in monitor_alerts.update(
+            failure.clone().into_iter().chain(fallback).collect(),
+            ctx.config.voting_rounds.round_duration,
+            Instant::now(),
+        ) {
+            info!("{}", format!("Sent message: {message}").red());
+            _ = ctx.sender.send_message(message).await;
+        }
+        // Wait for the backoff, the next pushed block or the rest of the poll interval
+        match backoff {
+            Some(backoff) => sleep_unless_stopped(backoff, &ctx.stop_flag).await,
+            None => {
+                let poll_wait = ctx.config.poll_interval.checked_sub(loop_start.elapsed());
+                head = new_heads
+                    .wait(&ctx.config.rpc_client, poll_wait, &ctx.stop_flag)
+                    .await
+                    .and_then(|head| Some((head.number?.as_u64(), head.timestamp.as_u64())));
+            }
+        }
+    }
+}
+
+impl MonitorContext {
+    // Swap in a reloaded config
+    fn reload(&mut self, new_config: RuntimeConfig) {
+        // The sightings of the window only cover the addresses and blocks it was filled for
+        let new_tracked_addresses = new_config.tracked_addresses();
+        if new_tracked_addresses != self.tracked_addresses
+            || new_config.block_window != self.config.block_window
+        {
+            self.state.window = SightingsWindow::new();
+            self.state.last_block = None;
+            self.state.window_start = None;
+            self.state.nonces.clear();
+        }
+        self.tracked_addresses = new_tracked_addresses;
+        // The new RPC may serve trace_filter
+        self.state.trace_unavailable = false;
+        // The contract registry may have changed
+        self.state.last_epoch_check = None;
+
+        self.sender = Sender {
+            token: new_config.tg_api_key.clone(),
+            chat_id: new_config.tg_chat_id.clone(),
+        };
+        self.alerts
+            .set_repeat_interval(Duration::from_secs(new_config.alert_repeat_interval));
+        self.alerts.retain(|key| {
+            key.identity.as_ref().map_or(true, |name| {
+                new_config
+                    .identities
+                    .iter()
+                    .any(|identity| &identity.name == name)
+            })
+        });
+        self.state.retain_identities(&new_config.identities);
+
+        self.config = new_config;
+    }
+
+    // One loop of the monitor: scan the new blocks, check every identity against them
+    // and send the alerts and the data to the metrics task
+    // The cursor only moves once all of it succeeded, so a failed loop is retried on the same head
+    async fn check(&mut self, head: Option<(u64, u64)>) -> Result<CheckOutcome, MonError> {
+        let MonitorContext {
+            config,
+            sender,
+            state,
+            alerts,
+            tracked_addresses,
+            monitoring_sender,
+            stop_flag,
+        } = self;
+
+        // 1. Get the current block, pushed over the WebSocket or asked for,
+        // and the window of "block-window" in the past
+        let block_num = match head {
+            Some((block_num, _)) => block_num,
+            None => config.rpc_client.latest_block_id().await?,
+        };
+        // An older block can come from a WebSocket node which lags behind
+        if let Some(last_block) = state
+            .last_block
+            .filter(|last_block| block_num <= *last_block)
+        {
+            let stalled_for = state
+                .head_moved_at
+                .get_or_insert_with(Instant::now)
+                .elapsed();
+            let stall_limit =
+                Duration::from_secs(STALLED_HEAD_ROUNDS * config.voting_rounds.round_duration);
+            if stalled_for >= stall_limit {
+                return Err(MonError::RpcClientError(anyhow::anyhow!(
+                    "The RPC head didn't move past block {last_block} for {}",
+                    format_duration(stalled_for)
+                )));
+            }
+            debug!("No new blocks after {block_num}... Waiting...");
+            return Ok(CheckOutcome::NoNewBlock);
+        }
+        state.head_moved_at = Some(Instant::now());
+
+        let now = match head {
+            Some((_, timestamp)) => timestamp,
+            None => config.rpc_client.get_block_timestamp(block_num).await?,
+        };
+
+        // Read the reward epoch from the FlareSystemsManager every now and then,
+        // the schedules of the flags are only used until it worked once
+        if state.last_epoch_check.map_or(true, |checked_at| {
+            checked_at.elapsed() >= EPOCH_CHECK_INTERVAL
+        }) {
+            state.last_epoch_check = Some(Instant::now());
+            match check_epoch(config).await {
+                Ok(epoch) => {
+                    debug!("Reward epoch: {epoch:?}");
+                    // The skipped blocks only matter to the duties which are still open
+                    state.unscanned.retain(|(_, skipped_until)| {
+                        epoch
+                            .opened_duties
+                            .iter()
+                            .any(|(_, _, opened_at)| skipped_until >= opened_at)
+                    });
+                    state.epoch = Some(epoch);
+                }
+                Err(err) => {
+                    let output = format!(
+                        "Couldn't read the reward epoch from the FlareSystemsManager because {err:?}!"
+                    )
+                    .red();
+                    error!("{output}");
+                }
+            }
+        }
+        if let Some(epoch) = &state.epoch {
+            config.voting_rounds = epoch.voting_rounds;
+            config.reward_epochs = epoch.reward_epochs;
+        }
+
+        let block_range_start = match config.block_window {
+            BlockWindow::Blocks(blocks) => block_num.saturating_sub(blocks),
+            // The window only moves forward, so the previous start bounds the search,
+            // the first one is bounded from the average block time
+            // The timestamps of the blocks in the window bound it further, so that only
+            // the blocks the window doesn't have are asked for
+            BlockWindow::Duration(duration) => {
+                let window_start_ts = now.saturating_sub(duration.as_secs());
+                let (after, at_latest) = state.window.bounds_since(window_start_ts);
+                let to = at_latest.unwrap_or(block_num).min(block_num);
+                let from = match after.max(state.window_start) {
+                    Some(from) => from,
+                    None => {
+                        config
+                            .rpc_client
+                            .block_before(window_start_ts, block_num, now)
+                            .await?
+                    }
+                };
+                config
+                    .rpc_client
+                    .first_block_since(window_start_ts, from.min(to), to)
+                    .await?
+            }
+        };
+        let block_range = block_range_start..block_num;
+
+        let colored_output = format!(
+            "Starting block_id: {block_num} and going back {} blocks for the last {} ({} mode)",
+            block_range.end - block_range.start,
+            config.block_window,
+            config.detection_mode
+        )
+        .green()
+        .underline();
+        info!("{colored_output}");
+
+        // The conditions which are wrong in this loop, sent once all of them are known
+        let mut firing = vec![];
+
+        // 2. Find the addresses which sent a tx within the window
+        let scan_start = Instant::now();
+        let unscanned = state.window.next_block(block_range.start)..block_range.end;
+        let new_blocks = match config.detection_mode {
+            // The new blocks only have to be fetched when one of the nonces moved
+            DetectionMode::Nonce => {
+                nonce_activity(config, tracked_addresses, &mut state.nonces, unscanned)
+                    .await
+                    .map(|active| match active.is_empty() {
+                        true => NewBlocks::Only(BTreeSet::new()),
+                        false => NewBlocks::All,
+                    })
+            }
+            DetectionMode::Trace if state.trace_unavailable => Ok(NewBlocks::All),
+            // Only the blocks with a tx from one of the addresses have to be fetched
+            DetectionMode::Trace => {
+                match trace_activity(config, tracked_addresses, unscanned).await {
+                    Ok(Some(block_ids)) => Ok(NewBlocks::Only(block_ids)),
+                    Ok(None) => {
+                        let output = "The RPC doesn't serve trace_filter! Scanning every block from now on...".red();
+                        warn!("{output}");
+                        state.trace_unavailable = true;
+                        Ok(NewBlocks::All)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            DetectionMode::Blocks => Ok(NewBlocks::All),
+        };
+        let new_blocks = new_blocks.unwrap_or_else(|err| {
+            let output = format!(
+                "Couldn't use the {} detection mode because {err:?}! Falling back to scanning blocks...",
+                config.detection_mode
+            )
+            .red();
+            warn!("{output}");
+            NewBlocks::All
+        });
+        // Every mode decodes the txs of the blocks it fetched, so they all count the same way
+        scan_blocks(
+            config,
+            state,
+            tracked_addresses,
+            block_range,
+            new_blocks,
+            stop_flag,
+        )
+        .await?;
+        let active = state.window.active();
+
+        let scan_duration = scan_start.elapsed().as_secs_f64();
+        debug!("Scanned the window in {scan_duration}s");
+
+        let tracking_since = *state.tracking_since.get_or_insert(now);
+        // The reward epoch only starts once its signing policy is signed, which the chain knows
+        let reward_epoch_id = match &state.epoch {
+            Some(epoch) => epoch.reward_epoch_id,
+            None => config
+                .reward_epochs
+                .reward_epoch_id(config.voting_rounds.round_id(now)),
+        };
+        let reward_epoch_changed = state
+            .current_reward_epoch
+            .is_some_and(|current| current != reward_epoch_id);
+        state.current_reward_epoch = Some(reward_epoch_id);
+
+        // The gas costs are only kept per voting round for the current reward epoch
+        state
+            .gas_costs
+            .prune_voting_rounds(config.reward_epochs.start_round(reward_epoch_id));
+        let gas_cost_summary = |gas_costs: &crate::types::GasCosts, address: &str| GasCostSummary {
+            total: gas_costs.total(address),
+            last_voting_round: gas_costs.voting_round(
+                config.voting_rounds.round_id(now).saturating_sub(1),
+                address,
+            ),
+            reward_epoch: gas_costs.reward_epoch(reward_epoch_id, address),
+        };
+
+        // 3. Check each identity against what was found
+        let epoch = state.epoch.clone();
+        // The last voting round checked, whichever identity it was checked for
+        let voting_round_id = Some(covered_rounds(config, &state.window))
+            .filter(|rounds| !rounds.is_empty())
+            .map_or(0, |rounds| rounds.end - 1);
+        let mut identities = Vec::with_capacity(config.identities.len());
+        for identity in &config.identities {
+            let name = &identity.name;
+            let identity_state = state.identities.entry(name.clone()).or_default();
+
+            // Get all the balances and estimate how long they last at the pace they drained recently
+            let mut balances = HashMap::new();
+            let mut runways = HashMap::new();
+            for role in AddressRole::ALL {
+                let balance = config
+                    .rpc_client
+                    .get_balance(identity.address(role).to_string())
+                    .await?;
+                firing.extend(balance_alert(
+                    name,
+                    role,
+                    balance,
+                    identity.balance_thresholds(role),
+                    config.hysteresis.balance,
+                ));
+
+                let history = identity_state.balance_histories.entry(role).or_default();
+                history.record(now, balance, config.burn_rate_window);
+                let runway = history.runway(MIN_BURN_RATE_SPAN.min(config.burn_rate_window));
+                debug!("{name} {role} runway: {runway:?}");
+                firing.extend(runway_alert(
+                    name,
+                    role,
+                    &runway,
+                    config.runway_warning_hours,
+                    config.hysteresis.runway,
+                ));
+
+                balances.insert(role, balance);
+                runways.insert(role, runway);
+
+                // Firing as long as a reverted tx is in the window
+                let reverted_txs = state.window.reverted_txs(identity.address(role));
+                if let Some(last_reverted_tx) = reverted_txs.last() {
+                    firing.push(Alert {
+                        key: AlertKey::identity(name, AlertCondition::Reverts(role)),
+                        severity: Severity::Warning,
+                        message: format!(
+                            "v2: {name}: {} {role} txs reverted within the last {}, the last one is {last_reverted_tx:?}!",
+                            reverted_txs.len(),
+                            config.block_window
+                        ),
+                        recovery: Some(format!(
+                            "v2: {name}: No {role} tx reverted within the last {}, resolved!",
+                            config.block_window
+                        )),
+                        hysteresis: config.hysteresis.reverts,
+                    });
+                }
+            }
+
+            let output = format!(
+                "{name} SPA, SA, SSA balances: {}, {}, {}",
+                format_flr(balances[&AddressRole::SigningPolicy]),
+                format_flr(balances[&AddressRole::Submit]),
+                format_flr(balances[&AddressRole::SubmitSignature])
+            )
+            .yellow();
+            info!("{output}");
+
+            let tx_found = |role| active.contains(&identity.address(role).to_lowercase());
+            let ssa_tx_found = tx_found(AddressRole::SubmitSignature);
+            let sa_tx_found = tx_found(AddressRole::Submit);
+            let spa_tx_found = tx_found(AddressRole::SigningPolicy);
+
+            // Only use the voting rounds if the window covers at least one of them
+            let participation = Some(check_rounds(config, identity, &state.window))
+                .filter(|participation| !participation.rounds.is_empty());
+            if let Some(participation) = &participation {
+                let output = format!(
+                    "{name} voting rounds {:?} missed by SA: {:?}, SSA: {:?}",
+                    participation.rounds,
+                    participation.submit_missed,
+                    participation.submit_signature_missed
+                )
+                .yellow();
+                info!("{output}");
+            }
+
+            // If the submit signature address didn't send submitSignatures in some voting rounds
+            // or, without the voting rounds, a tx from it was not found
+            for (role, missed, tx_found) in [
+                (
+                    AddressRole::SubmitSignature,
+                    participation
+                        .as_ref()
+                        .map(|participation| &participation.submit_signature_missed),
+                    ssa_tx_found,
+                ),
+                (
+                    AddressRole::Submit,
+                    participation
+                        .as_ref()
+                        .map(|participation| &participation.submit_missed),
+                    sa_tx_found,
+                ),
+            ] {
+                let message = match (&participation, missed) {
+                    (Some(participation), Some(missed)) if !missed.is_empty() => format!(
+                        "v2: {name}: {role} missed {} of the last {} voting rounds: {missed:?}!",
+                        missed.len(),
+                        participation.rounds.end - participation.rounds.start,
+                    ),
+                    (None, _) if !tx_found => format!(
+                        "v2: {name}: {role} has not signed within the last {}!",
+                        config.block_window
+                    ),
+                    _ => continue,
+                };
+                firing.push(Alert {
+                    key: AlertKey::identity(name, AlertCondition::Participation(role)),
+                    severity: Severity::Critical,
+                    message,
+                    recovery: Some(format!("v2: {name}: {role} is submitting again, resolved!")),
+                    hysteresis: config.hysteresis.participation,
+                });
+            }
+
+            // Check the voter registration of the identity address every now and then
+            if let Some(identity_address) = &identity.identity_address {
+                if identity_state
+                    .last_registration_check
+                    .map_or(true, |checked_at| {
+                        checked_at.elapsed() >= REGISTRATION_CHECK_INTERVAL
+                    })
+                {
+                    match check_registration(config, identity_address).await {
+                        Ok(registration) => {
+                            let output = format!("{name} registration: {registration:?}").yellow();
+                            info!("{output}");
+
+                            identity_state.registration = registration;
+                            identity_state.last_registration_check = Some(Instant::now());
+                        }
+                        Err(err) => {
+                            let output =
+                                format!("Couldn't check the voter registration of {name}: {err:?}")
+                                    .red();
+                            error!("{output}");
+                        }
+                    }
+                }
+            }
+
+            // The registration closes once the new signing policy is initialized
+            // and if that happens before registering, the alert is cleared without a recovery
+            let registration = &identity_state.registration;
+            let registration_key = AlertKey::identity(
+                name,
+                AlertCondition::Registration(registration.reward_epoch_id + 1),
+            );
+            if !registration.registered_for_next_epoch
+                && (registration.next_epoch_registration_open
+                    || alerts.is_firing(&registration_key))
+            {
+                firing.push(Alert {
+                    key: registration_key,
+                    severity: Severity::Critical,
+                    message: format!(
+                    "v2: {name}: Not registered for reward epoch {} and the registration is {}!",
+                    registration.reward_epoch_id + 1,
+                    if registration.next_epoch_registration_open {
+                        "open"
+                    } else {
+                        "closed"
+                    }
+                ),
+                    recovery: registration.next_epoch_registration_open.then(|| {
+                        format!(
+                            "v2: {name}: Registered for reward epoch {}, resolved!",
+                            registration.reward_epoch_id + 1
+                        )
+                    }),
+                    hysteresis: config.hysteresis.registration,
+                });
+            }
+
+            // The signing policy address only has to sign a few things per reward epoch,
+            // so it's only checked against the duties due right now
+            let duties = epoch
+                .as_ref()
+                .map(|epoch| {
+                    check_duties(
+                        config,
+                        identity,
+                        identity_state,
+                        epoch,
+                        now,
+                        tracking_since,
+                        &state.unscanned,
+                    )
+                })
+                .unwrap_or_default();
+
+            let output = format!("{name} signing policy duties due: {duties:?}").yellow();
+            debug!("{output}");
+
+            // If a due signature from the signing policy address is past its deadline,
+            // with a warning before the deadline
+            // When the monitor skipped blocks since the signing opened, the call may be in them
+            for duty in &duties {
+                let (severity, message) = if duty.missed(now) && duty.unscanned {
+                    (
+                        Severity::Warning,
+                        format!(
+                        "v2: {name}: Signing Policy Address wasn't seen calling {} for reward epoch {}, but the monitor skipped blocks since its signing opened!",
+                        duty.duty, duty.reward_epoch_id
+                    ),
+                    )
+                } else if duty.missed(now) {
+                    (
+                        Severity::Critical,
+                        format!(
+                        "v2: {name}: Signing Policy Address has not called {} for reward epoch {}!",
+                        duty.duty, duty.reward_epoch_id
+                    ),
+                    )
+                } else if duty.deadline_approaching(now, config.duty_deadlines.warning) {
+                    (
+                    Severity::Warning,
+                    format!(
+                        "v2: {name}: Signing Policy Address has not called {} for reward epoch {} yet and the deadline is in {} minutes!",
+                        duty.duty,
+                        duty.reward_epoch_id,
+                        (duty.deadline - now) / 60
+                    ),
+                )
+                } else {
+                    continue;
+                };
+                // A missed duty stops firing when its reward epoch is over, not because it was done
+                let recovery = (!duty.missed(now)).then(|| {
+                    format!(
+                    "v2: {name}: Signing Policy Address called {} for reward epoch {}, resolved!",
+                    duty.duty, duty.reward_epoch_id
+                )
+                });
+                firing.push(Alert {
+                    key: AlertKey::identity(
+                        name,
+                        AlertCondition::Duty(duty.duty, duty.reward_epoch_id),
+                    ),
+                    severity,
+                    message,
+                    recovery,
+                    hysteresis: config.hysteresis.duty,
+                });
+            }
+
+            // When a reward epoch starts, all the duties of the one before the previous are over
+            if reward_epoch_changed {
+                if let Some(finished_epoch_id) = reward_epoch_id.checked_sub(2) {
+                    let output = format!(
+                    "{name} signing duties performed for reward epoch {finished_epoch_id}: {:?}",
+                    identity_state.signing_duties.done_for(finished_epoch_id)
+                )
+                    .yellow();
+                    info!("{output}");
+                }
+                if let Some(finished_epoch_id) = reward_epoch_id.checked_sub(1) {
+                    let spent = |role| {
+                        format_flr(
+                            state
+                                .gas_costs
+                                .reward_epoch(finished_epoch_id, identity.address(role)),
+                        )
+                    };
+                    let output = format!(
+                    "{name} gas spent in reward epoch {finished_epoch_id} by SPA, SA, SSA: {}, {}, {} FLR",
+                    spent(AddressRole::SigningPolicy),
+                    spent(AddressRole::Submit),
+                    spent(AddressRole::SubmitSignature),
+                )
+                .yellow();
+                    info!("{output}");
+                }
+            }
+
+            // Only the previous reward epoch is still needed
+            identity_state
+                .signing_duties
+                .prune(reward_epoch_id.saturating_sub(1));
+
+            // Gather all the relevant data
+            // The registration is only read for the identities with an identity address
+            let registration = identity
+                .identity_address
+                .as_ref()
+                .and(identity_state.last_registration_check)
+                .map(|_| &identity_state.registration);
+            let tx_outcomes = &state.tx_outcomes;
+            let gas_costs = &state.gas_costs;
+            identities.push(IdentityData {
+                name: name.clone(),
+                signing_policy_tx_found: spa_tx_found,
+                signing_policy_tx_successful: tx_outcomes
+                    .successful(&identity.signing_policy_address),
+                signing_policy_tx_reverted: tx_outcomes.reverted(&identity.signing_policy_address),
+                signing_policy_balance: balances[&AddressRole::SigningPolicy],
+                signing_policy_runway: runways[&AddressRole::SigningPolicy],
+                signing_policy_gas_cost: gas_cost_summary(
+                    gas_costs,
+                    &identity.signing_policy_address,
+                ),
+                submit_tx_found: sa_tx_found,
+                submit_tx_successful: tx_outcomes.successful(&identity.submit_address),
+                submit_tx_reverted: tx_outcomes.reverted(&identity.submit_address),
+                submit_balance: balances[&AddressRole::Submit],
+                submit_runway: runways[&AddressRole::Submit],
+                submit_gas_cost: gas_cost_summary(gas_costs, &identity.submit_address),
+                submit_signature_tx_found: ssa_tx_found,
+                submit_signature_tx_successful: tx_outcomes
+                    .successful(&identity.submit_signature_address),
+                submit_signature_tx_reverted: tx_outcomes
+                    .reverted(&identity.submit_signature_address),
+                submit_signature_balance: balances[&AddressRole::SubmitSignature],
+                submit_signature_runway: runways[&AddressRole::SubmitSignature],
+                submit_signature_gas_cost: gas_cost_summary(
+                    gas_costs,
+                    &identity.submit_signature_address,
+                ),
+                submit_missed_rounds: participation
+                    .as_ref()
+                    .map(|participation| participation.submit_missed.len() as u64)
+                    .unwrap_or_default(),
+                submit_signature_missed_rounds: participation
+                    .as_ref()
+                    .map(|participation| participation.submit_signature_missed.len() as u64)
+                    .unwrap_or_default(),
+                submission_stats: identity_state.submission_stats.clone(),
+                signing_duty_last_reward_epochs: identity_state.signing_duties.last_reward_epochs(),
+                signing_duties: duties,
+                registered_for_this_epoch: registration
+                    .map(|registration| registration.registered_for_this_epoch),
+                registered_for_next_epoch: registration
+                    .map(|registration| registration.registered_for_next_epoch),
+            });
+        }
+
+        for message in alerts.update(firing, config.voting_rounds.round_duration, Instant::now()) {
+            info!("{}", format!("Sent message: {message}").red());
+            _ = sender.send_message(message).await;
+        }
+
+        let data = Data {
+            scan_duration,
+            window_blocks: block_range_start.abs_diff(block_num),
+            voting_round_id,
+            reward_epoch_id,
+            block_timestamp: now,
+            gas_costs: state.gas_costs.clone(),
+            identities,
+            monitor_errors: state.errors,
+            monitor_healthy: true,
+            reorgs: state.reorgs,
+        };
+
+        // Send it to the metrics task
+        match monitoring_sender.send(data.clone()) {
+            Ok(_) => {
+                debug!("Sent data: {data:?}");
+            }
+            Err(e) => {
+                error!("{}", format!("Couldn't send to metrics task: {e:?}").red());
+            }
+        }
+
+        state.last_block = Some(block_num);
+        state.window_start = Some(block_range_start);
+        Ok(CheckOutcome::Checked)
+    }
+}
+
+fn retry_backoff(failures: u32) -> Duration {
+    RETRY_BACKOFF_MIN
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(RETRY_BACKOFF_MAX)
+}
+
+// Sleep for `duration`, or less if the monitor has to stop
+pub(super) async fn sleep_unless_stopped(duration: Duration, stop_flag: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+    while !stop_flag.load(Ordering::Relaxed) && Instant::now() < deadline {
+        tokio::time::sleep(STOP_CHECK_INTERVAL.min(deadline - Instant::now())).await;
+    }
+}
@@ -0,0 +1,677 @@
+use colored::Colorize;
+use std::{
+    collections::BTreeSet,
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use web3::{
+    futures::{stream, StreamExt},
+    types::{Block, Transaction, H256},
+};
+
+use tracing::{debug, error, info, warn};
+
+use super::state::MonitorState;
+use crate::types::{
+    AddressRole, BlockSightings, MonError, RuntimeConfig, ScannedTx, Sighting, SigningCall,
+    SubmissionCall,
+};
+
+// How many times the new blocks are fetched again after a reorg in one loop,
+// the rest waits for the next loop if the RPC keeps switching between forks
+const MAX_REORG_RESCANS: u32 = 3;
+
+// Which of the new blocks have to be fetched
+#[derive(Debug, Clone)]
+pub(crate) enum NewBlocks {
+    All,
+    // Only the blocks which may have a tx from a tracked address, the header of the last new block
+    // is still fetched to move the cursor and keep the timestamps of the window
+    Only(BTreeSet<u64>),
+}
+
+impl NewBlocks {
+    fn contains(&self, block_id: u64) -> bool {
+        match self {
+            NewBlocks::All => true,
+            NewBlocks::Only(block_ids) => block_ids.contains(&block_id),
+        }
+    }
+}
+
+// What the scan keeps of a fetched block
+// The txs are only fetched for the blocks which may have one from a tracked address
+struct FetchedBlock {
+    hash: Option<H256>,
+    parent_hash: H256,
+    timestamp: u64,
+    transactions: Vec<Transaction>,
+}
+
+impl From<Block<Transaction>> for FetchedBlock {
+    fn from(block: Block<Transaction>) -> FetchedBlock {
+        FetchedBlock {
+            hash: block.hash,
+            parent_hash: block.parent_hash,
+            timestamp: block.timestamp.as_u64(),
+            transactions: block.transactions,
+        }
+    }
+}
+
+impl From<Block<H256>> for FetchedBlock {
+    fn from(block: Block<H256>) -> FetchedBlock {
+        FetchedBlock {
+            hash: block.hash,
+            parent_hash: block.parent_hash,
+            timestamp: block.timestamp.as_u64(),
+            transactions: vec![],
+        }
+    }
+}
+
+// Fetch the blocks of `block_range` which are not in the window yet and record
+// which of the `tracked_addresses` sent a tx in each of them
+// `tracked_addresses` are the addresses of all the identities
+// Txs from the submit and submit signature addresses only count when they are Submission calls
+// and the receipt of each of them is fetched to find the ones which reverted
+// A block whose parent isn't the block in the window means the chain was reorganised,
+// the blocks which were replaced are rolled back and fetched again
+pub(crate) async fn scan_blocks(
+    config: &RuntimeConfig,
+    state: &mut MonitorState,
+    tracked_addresses: &[String],
+    block_range: Range<u64>,
+    mut only: NewBlocks,
+    stop_flag: &AtomicBool,
+) -> Result<(), MonError> {
+    let submission_address = config
+        .submission_address
+        .as_ref()
+        .map(|address| address.to_lowercase());
+
+    // The blocks between the cursor and the window are skipped when the loops failed
+    // for longer than the window, the time they cover is kept to know what may have been missed
+    let mut skipped_since = state
+        .window
+        .cursor()
+        .filter(|cursor| cursor + 1 < block_range.start)
+        .and_then(|_| state.window.last_timestamp());
+
+    let mut rescans = 0;
+    loop {
+        // The parent check below only covers a block fetched right after the one before it,
+        // so when blocks are skipped the cursor itself is checked against the chain first
+        let mut reorged_block = match only {
+            NewBlocks::All => None,
+            NewBlocks::Only(_) => replaced_cursor(config, state).await?,
+        };
+
+        // Only the blocks after the cursor have to be fetched
+        // An empty window also gets its first block, which the voting rounds start from,
+        // and so does one after skipped blocks, which tells where they end
+        let new_blocks = state.window.next_block(block_range.start)..block_range.end;
+        let first_block =
+            (state.window.is_empty() || skipped_since.is_some()).then_some(new_blocks.start);
+        let last_block = new_blocks.end.checked_sub(1);
+        // With whether their txs are needed
+        let new_blocks: Vec<(u64, bool)> = match reorged_block {
+            Some(_) => vec![],
+            None => new_blocks
+                .map(|block_id| (block_id, only.contains(block_id)))
+                .filter(|&(block_id, with_txs)| {
+                    with_txs || Some(block_id) == first_block || Some(block_id) == last_block
+                })
+                .collect(),
+        };
+
+        if reorged_block.is_none() {
+            let colored_output = format!(
+                "Fetching {} new blocks of the last {}",
+                new_blocks.len(),
+                config.block_window
+            )
+            .green();
+            info!("{colored_output}");
+        }
+
+        // Fetch the new blocks, at most "fetch-concurrency" of them at a time
+        // `buffered` hands the blocks back in order, so the cursor still moves block by block
+        // The blocks which can't have a tx from a tracked address are fetched without their txs
+        let rpc_client = config.rpc_client.clone();
+        let mut blocks = stream::iter(new_blocks)
+            .map(|(block_id, with_txs)| {
+                let rpc_client = rpc_client.clone();
+                async move {
+                    let block = match with_txs {
+                        true => rpc_client
+                            .get_block(block_id)
+                            .await
+                            .map(|block| block.map(FetchedBlock::from)),
+                        false => rpc_client
+                            .get_block_header(block_id)
+                            .await
+                            .map(|block| block.map(FetchedBlock::from)),
+                    };
+                    (block_id, block)
+                }
+            })
+            .buffered(config.fetch_concurrency);
+
+        'blocks: while let Some((block_id, block)) = blocks.next().await {
+            // When stopping, the loop goes on with the blocks scanned so far
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+
+            // Get the block's contents
+            let block = match block {
+                Ok(block) => block,
+                Err(err) => {
+                    let output = format!(
+                        "Couldn't get block contents of {} because {:?}!",
+                        block_id, err
+                    )
+                    .red();
+                    error!("{output}");
+                    None
+                }
+            };
+
+            // If block is not found, stop here and retry it in the next loop
+            // so that the cursor never skips over a block
+            let Some(block) = block else {
+                warn!("Couldn't get block {}... Retrying next loop...", block_id);
+                break;
+            };
+
+            // The previous block in the window was replaced if it isn't the parent of this one
+            if let Some(parent_hash) = block_id
+                .checked_sub(1)
+                .and_then(|parent_id| state.window.hash(parent_id))
+            {
+                if parent_hash != block.parent_hash {
+                    reorged_block = Some(block_id - 1);
+                    break;
+                }
+            }
+
+            // Find the txs sent by the tracked addresses
+            let mut matched = vec![];
+            for mut tx in block.transactions {
+                let Some(from) = tx.from.take() else {
+                    continue;
+                };
+                let from_address = format!("{:?}", from).to_lowercase();
+
+                if tracked_addresses.contains(&from_address) {
+                    matched.push((from_address, tx));
+                }
+            }
+
+            // Get all the receipts before recording anything, so that a block
+            // which has to be retried isn't counted twice
+            let mut statuses = Vec::with_capacity(matched.len());
+            for (_, tx) in &matched {
+                match config.rpc_client.get_receipt(tx.hash).await {
+                    Ok(Some(receipt)) => {
+                        let reverted = receipt.status.is_some_and(|status| status.is_zero());
+                        // Older receipts don't have the effective gas price
+                        let gas_price = receipt
+                            .effective_gas_price
+                            .or(tx.gas_price)
+                            .unwrap_or_default();
+                        let cost = receipt
+                            .gas_used
+                            .unwrap_or_default()
+                            .saturating_mul(gas_price);
+                        statuses.push((reverted, cost))
+                    }
+                    Ok(None) => {
+                        warn!("Couldn't get the receipt of {:?}... Retrying block {block_id} next loop...", tx.hash);
+                        break 'blocks;
+                    }
+                    Err(err) => {
+                        let output = format!(
+                            "Couldn't get the receipt of {:?} because {err:?}! Retrying block {block_id} next loop...",
+                            tx.hash
+                        )
+                        .red();
+                        error!("{output}");
+                        break 'blocks;
+                    }
+                }
+            }
+
+            // Go through each tx and find the ones for the ftso
+            let mut sightings = BlockSightings {
+                timestamp: block.timestamp,
+                hash: block.hash.unwrap_or_default(),
+                parent_hash: block.parent_hash,
+                sightings: vec![],
+                txs: vec![],
+            };
+            let voting_round_id = config.voting_rounds.round_id(sightings.timestamp);
+            let reward_epoch_id = config.reward_epochs.reward_epoch_id(voting_round_id);
+            for ((from_address, tx), (reverted, cost)) in matched.into_iter().zip(statuses) {
+                // Reverted txs still pay for their gas
+                state
+                    .gas_costs
+                    .record(&from_address, voting_round_id, reward_epoch_id, cost);
+
+                // Reverted txs (e.g. late submissions or out of gas) don't do anything
+                state.tx_outcomes.record(&from_address, reverted);
+                sightings.txs.push(ScannedTx {
+                    from: from_address.clone(),
+                    tx_hash: tx.hash,
+                    reverted,
+                    cost,
+                });
+                if reverted {
+                    warn!(
+                        "Tx {:?} from {from_address} in block {block_id} reverted",
+                        tx.hash
+                    );
+                }
+
+                let Some((identity, role)) = config.identity_of(&from_address) else {
+                    continue;
+                };
+                if role == AddressRole::SigningPolicy {
+                    let signing_call = SigningCall::decode(&tx.input.0).filter(|_| !reverted);
+                    if let Some(signing_call) = &signing_call {
+                        debug!(
+                            "{} for reward epoch {} from {from_address} in block {block_id}",
+                            signing_call.duty, signing_call.reward_epoch_id
+                        );
+                        state
+                            .identity(&identity.name)
+                            .signing_duties
+                            .record(signing_call);
+                    }
+
+                    sightings.sightings.push(Sighting {
+                        from: from_address,
+                        tx_hash: tx.hash,
+                        call: None,
+                        signing_call,
+                        reverted,
+                    });
+                    continue;
+                }
+
+                // Anything else than a Submission call (e.g. a transfer) doesn't count as a submission
+                let to_address = tx.to.map(|to| format!("{:?}", to).to_lowercase());
+                let call = match &submission_address {
+                    Some(submission_address) if to_address.as_ref() != Some(submission_address) => {
+                        None
+                    }
+                    _ => SubmissionCall::decode(&tx.input.0),
+                };
+                let Some(call) = call else {
+                    debug!(
+                        "Ignoring tx {:?} from {from_address} which isn't a Submission call",
+                        tx.hash
+                    );
+                    continue;
+                };
+
+                debug!(
+                    "{} from {from_address} in block {block_id} with payloads {:?}",
+                    call.function, call.payloads
+                );
+                if !reverted {
+                    state
+                        .identity(&identity.name)
+                        .submission_stats
+                        .record(&call);
+                }
+                sightings.sightings.push(Sighting {
+                    from: from_address,
+                    tx_hash: tx.hash,
+                    call: Some(call),
+                    signing_call: None,
+                    reverted,
+                });
+            }
+
+            if let Some(skipped_since) = skipped_since.take() {
+                let output =
+                    format!("Skipped the blocks before {block_id} which fell out of the window!")
+                        .red();
+                warn!("{output}");
+                state.unscanned.push((skipped_since, sightings.timestamp));
+            }
+            state.window.insert(block_id, sightings);
+        }
+
+        let Some(reorged_block) = reorged_block else {
+            break;
+        };
+        let first_reorged_block =
+            first_reorged_block(config, state, reorged_block, block_range.start).await?;
+        rollback(config, state, first_reorged_block);
+        // Which blocks of the new fork have txs isn't known
+        only = NewBlocks::All;
+
+        rescans += 1;
+        if rescans > MAX_REORG_RESCANS {
+            warn!("The chain keeps reorganising... Scanning the new blocks next loop...");
+            break;
+        }
+    }
+
+    // Forget the blocks which are no longer in the window
+    state.window.prune(block_range.start);
+
+    Ok(())
+}
+
+// The cursor if the chain doesn't have the block the window ends with anymore
+async fn replaced_cursor(
+    config: &RuntimeConfig,
+    state: &MonitorState,
+) -> Result<Option<u64>, MonError> {
+    let Some((cursor, hash)) = state
+        .window
+        .cursor()
+        .and_then(|cursor| Some((cursor, state.window.hash(cursor)?)))
+    else {
+        return Ok(None);
+    };
+
+    let canonical_hash = config.rpc_client.get_block_hash(cursor).await?;
+    Ok((canonical_hash != Some(hash)).then_some(cursor))
+}
+
+// Walk back from `reorged_block`, which was replaced, through the blocks of the window
+// until one is still canonical, everything after it has to be scanned again
+// (the blocks skipped in between included, as the new fork may have txs in them)
+// If none of them is, the reorg is deeper than the window and all of it is scanned again
+async fn first_reorged_block(
+    config: &RuntimeConfig,
+    state: &MonitorState,
+    reorged_block: u64,
+    window_start: u64,
+) -> Result<u64, MonError> {
+    for (block_id, hash) in state.window.hashes_before(reorged_block) {
+        if config.rpc_client.get_block_hash(block_id).await? == Some(hash) {
+            return Ok(block_id + 1);
+        }
+    }
+
+    Ok(window_start.min(reorged_block))
+}
+
+// Drop the blocks from `first_reorged_block` on and take back everything recorded for them,
+// so that they can be scanned again from the canonical chain
+fn rollback(config: &RuntimeConfig, state: &mut MonitorState, first_reorged_block: u64) {
+    let dropped = state.window.rollback(first_reorged_block);
+    let last_reorged_block = dropped
+        .keys()
+        .next_back()
+        .copied()
+        .unwrap_or(first_reorged_block);
+    let output = format!(
+        "Chain reorganisation: blocks {first_reorged_block} to {last_reorged_block} were replaced! Scanning them again..."
+    )
+    .yellow();
+    warn!("{output}");
+    state.reorgs += 1;
+
+    for block in dropped.into_values() {
+        let voting_round_id = config.voting_rounds.round_id(block.timestamp);
+        let reward_epoch_id = config.reward_epochs.reward_epoch_id(voting_round_id);
+        for tx in &block.txs {
+            state
+                .gas_costs
+                .unrecord(&tx.from, voting_round_id, reward_epoch_id, tx.cost);
+            state.tx_outcomes.unrecord(&tx.from, tx.reverted);
+        }
+
+        for sighting in block.sightings.iter().filter(|sighting| !sighting.reverted) {
+            let Some((identity, _)) = config.identity_of(&sighting.from) else {
+                continue;
+            };
+            let identity_state = state.identity(&identity.name);
+            if let Some(call) = &sighting.call {
+                identity_state.submission_stats.unrecord(call);
+            }
+            if let Some(signing_call) = &sighting.signing_call {
+                identity_state.signing_duties.unrecord(signing_call);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        monitor::nonce_activity::nonce_activity,
+        rpc::RpcClient,
+        types::{
+            AlertHysteresis, BlockWindow, DetectionMode, DutyDeadlines, Identity,
+            RewardEpochSchedule, VotingRoundSchedule,
+        },
+    };
+
+    type Requests = Arc<Mutex<Vec<(String, Value)>>>;
+
+    // A node serving a chain without txs on a local port, which keeps every request it got
+    fn mock_node() -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Requests::default();
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let log = log.clone();
+                std::thread::spawn(move || serve(stream, &log));
+            }
+        });
+        (url, requests)
+    }
+
+    fn serve(mut stream: TcpStream, log: &Mutex<Vec<(String, Value)>>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        loop {
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return;
+                }
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let method = request["method"].as_str().unwrap().to_string();
+            let params = request["params"].clone();
+            let result = match method.as_str() {
+                "eth_getBlockByNumber" => {
+                    let block_id = params[0].as_str().unwrap().trim_start_matches("0x");
+                    block(u64::from_str_radix(block_id, 16).unwrap())
+                }
+                "eth_getTransactionCount" => json!("0x5"),
+                _ => Value::Null,
+            };
+            log.lock().unwrap().push((method, params));
+
+            let response =
+                json!({"jsonrpc": "2.0", "id": request["id"], "result": result}).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{response}",
+                response.len()
+            )
+            .unwrap();
+        }
+    }
+
+    fn block(block_id: u64) -> Value {
+        let hash = |block_id: u64| format!("0x{block_id:064x}");
+        json!({
+            "number": format!("0x{block_id:x}"),
+            "hash": hash(block_id + 1),
+            "parentHash": hash(block_id),
+            "timestamp": format!("0x{:x}", 1700000000 + block_id * 2),
+            "transactions": [],
+            "sha3Uncles": hash(0),
+            "miner": format!("0x{:040x}", 0),
+            "stateRoot": hash(0),
+            "transactionsRoot": hash(0),
+            "receiptsRoot": hash(0),
+            "gasUsed": "0x0",
+            "gasLimit": "0x0",
+            "extraData": "0x",
+            "difficulty": "0x0",
+            "uncles": [],
+        })
+    }
+
+    fn config(rpc_url: String) -> RuntimeConfig {
+        RuntimeConfig {
+            tg_api_key: String::new(),
+            tg_chat_id: String::new(),
+            rpc_client: RpcClient::new(vec![rpc_url], None).unwrap(),
+            block_window: BlockWindow::Blocks(100),
+            poll_interval: Duration::from_secs(10),
+            fetch_concurrency: 4,
+            detection_mode: DetectionMode::Blocks,
+            voting_rounds: VotingRoundSchedule {
+                first_round_start_ts: 1658430000,
+                round_duration: 90,
+            },
+            reward_epochs: RewardEpochSchedule {
+                first_epoch_start_round: 0,
+                epoch_duration_rounds: 3360,
+            },
+            duty_deadlines: DutyDeadlines {
+                uptime_vote: 7200,
+                rewards: 7200,
+                signing_policy: 7200,
+                warning: 1800,
+            },
+            identities: vec![Identity::from_str("a=0x0000000000000000000000000000000000000001,0x0000000000000000000000000000000000000002,0x0000000000000000000000000000000000000003").unwrap()],
+            burn_rate_window: 86400,
+            runway_warning_hours: 72,
+            alert_repeat_interval: 3600,
+            hysteresis: AlertHysteresis::default(),
+            submission_address: None,
+            contract_registry_address: String::from("0xaD67FE66660Fb8dFE9d6b1b4240d8650e30F6019"),
+        }
+    }
+
+    // One loop over `block_range`, as the monitor runs it in `detection_mode`,
+    // with the requests it made
+    async fn scan_loop(
+        config: &RuntimeConfig,
+        requests: &Requests,
+        state: &mut MonitorState,
+        block_range: Range<u64>,
+    ) -> Vec<(String, Value)> {
+        requests.lock().unwrap().clear();
+        let tracked_addresses = config.tracked_addresses();
+        let unscanned = state.window.next_block(block_range.start)..block_range.end;
+        let only = match config.detection_mode {
+            DetectionMode::Nonce => {
+                let active =
+                    nonce_activity(config, &tracked_addresses, &mut state.nonces, unscanned)
+                        .await
+                        .unwrap();
+                match active.is_empty() {
+                    true => NewBlocks::Only(BTreeSet::new()),
+                    false => NewBlocks::All,
+                }
+            }
+            _ => NewBlocks::All,
+        };
+        let stop_flag = AtomicBool::new(false);
+        scan_blocks(
+            config,
+            state,
+            &tracked_addresses,
+            block_range,
+            only,
+            &stop_flag,
+        )
+        .await
+        .unwrap();
+        requests.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn nonce_mode_makes_fewer_requests_than_blocks_mode() {
+        let (rpc_url, requests) = mock_node();
+        let blocks_config = config(rpc_url);
+        let mut nonce_config = blocks_config.clone();
+        nonce_config.detection_mode = DetectionMode::Nonce;
+
+        let mut blocks_state = MonitorState::default();
+        let mut nonce_state = MonitorState::default();
+        scan_loop(&blocks_config, &requests, &mut blocks_state, 100..110).await;
+        scan_loop(&nonce_config, &requests, &mut nonce_state, 100..110).await;
+        assert_eq!(nonce_state.window.cursor(), Some(109));
+
+        let blocks_requests =
+            scan_loop(&blocks_config, &requests, &mut blocks_state, 110..120).await;
+        let nonce_requests = scan_loop(&nonce_config, &requests, &mut nonce_state, 110..120).await;
+        assert_eq!(blocks_requests.len(), 10);
+        assert!(
+            nonce_requests.len() < blocks_requests.len(),
+            "{nonce_requests:?}"
+        );
+        assert_eq!(nonce_state.window.cursor(), Some(119));
+
+        // One nonce per address, the one before the blocks is the one of the previous loop
+        let nonces = nonce_requests
+            .iter()
+            .filter(|(method, _)| method == "eth_getTransactionCount")
+            .count();
+        assert_eq!(nonces, 3);
+        // The cursor only needs the headers
+        assert!(nonce_requests
+            .iter()
+            .filter(|(method, _)| method == "eth_getBlockByNumber")
+            .all(|(_, params)| params[1] == json!(false)));
+    }
+
+    #[tokio::test]
+    async fn skipped_blocks_are_kept() {
+        let (rpc_url, requests) = mock_node();
+        let config = config(rpc_url);
+        let mut state = MonitorState::default();
+        scan_loop(&config, &requests, &mut state, 100..110).await;
+        scan_loop(&config, &requests, &mut state, 110..120).await;
+        assert!(state.unscanned.is_empty());
+
+        // The window moved past the cursor
+        scan_loop(&config, &requests, &mut state, 200..210).await;
+        assert_eq!(
+            state.unscanned,
+            [(1700000000 + 119 * 2, 1700000000 + 200 * 2)]
+        );
+        assert_eq!(state.window.cursor(), Some(209));
+    }
+}
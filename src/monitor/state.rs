@@ -0,0 +1,74 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Instant,
+};
+
+use super::{check_epoch::EpochInfo, check_registration::Registration};
+use crate::types::{
+    AddressRole, BalanceHistory, GasCosts, Identity, SightingsWindow, SigningDutyTracker,
+    SubmissionStats, TxOutcomes,
+};
+
+// What the monitor learned from the chain and keeps between loops
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MonitorState {
+    // Blocks which were already fetched
+    pub window: SightingsWindow,
+    pub tx_outcomes: TxOutcomes,
+    pub gas_costs: GasCosts,
+    // The timestamp of the first block checked
+    pub tracking_since: Option<u64>,
+    pub current_reward_epoch: Option<u64>,
+    // The schedules and the signing phases read from the chain
+    pub epoch: Option<EpochInfo>,
+    pub last_epoch_check: Option<Instant>,
+    pub last_block: Option<u64>,
+    // When a block after the last one was last seen
+    pub head_moved_at: Option<Instant>,
+    // The first block of the last window, where the next one starts looking
+    pub window_start: Option<u64>,
+    // The nonce of each address at a block, the end of the blocks the nonce mode last checked
+    pub nonces: HashMap<String, (u64, u64)>,
+    // Set once the RPC answered that it doesn't serve trace_filter
+    pub trace_unavailable: bool,
+    // The loops which failed since the start
+    pub errors: u64,
+    // The chain reorganisations found in the window since the start
+    pub reorgs: u64,
+    // The timestamps between which blocks were skipped, as the loops failed for longer than the window
+    pub unscanned: Vec<(u64, u64)>,
+    // Keyed by the name of the identity
+    pub identities: BTreeMap<String, IdentityState>,
+}
+
+// What the monitor keeps between loops for each identity
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdentityState {
+    pub submission_stats: SubmissionStats,
+    pub signing_duties: SigningDutyTracker,
+    pub registration: Registration,
+    pub last_registration_check: Option<Instant>,
+    pub balance_histories: HashMap<AddressRole, BalanceHistory>,
+}
+
+impl MonitorState {
+    pub fn new(identities: &[Identity]) -> MonitorState {
+        MonitorState {
+            identities: identities
+                .iter()
+                .map(|identity| (identity.name.clone(), IdentityState::default()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    // Drop the state of the identities which aren't monitored anymore
+    pub fn retain_identities(&mut self, identities: &[Identity]) {
+        self.identities
+            .retain(|name, _| identities.iter().any(|identity| &identity.name == name));
+    }
+
+    pub fn identity(&mut self, name: &str) -> &mut IdentityState {
+        self.identities.entry(name.to_string()).or_default()
+    }
+}
@@ -0,0 +1,36 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use tracing::debug;
+
+use crate::types::{MonError, RuntimeConfig};
+
+// Find the blocks within `block_range` in which one of the `tracked_addresses` sent a tx
+// with `trace_filter`, reverted txs included so that they're counted like in the blocks mode
+// None if the RPC doesn't serve trace_filter
+pub(crate) async fn trace_activity(
+    config: &RuntimeConfig,
+    tracked_addresses: &[String],
+    block_range: Range<u64>,
+) -> Result<Option<BTreeSet<u64>>, MonError> {
+    if block_range.is_empty() {
+        return Ok(Some(BTreeSet::new()));
+    }
+
+    let Some(traces) = config
+        .rpc_client
+        .filter_traces(tracked_addresses, block_range.start, block_range.end - 1)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    // Only the top level call of a trace is the tx itself
+    let block_ids: BTreeSet<u64> = traces
+        .iter()
+        .filter(|trace| trace.trace_address.is_empty())
+        .map(|trace| trace.block_number)
+        .collect();
+    debug!("Found {} traces in blocks {block_ids:?}", traces.len());
+
+    Ok(Some(block_ids))
+}
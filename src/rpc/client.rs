@@ -1,64 +1,380 @@
-use std::str::FromStr;
+use std::{
+    future::Future,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use anyhow::Context;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use tracing::{debug, info, warn};
 use web3::{
-    transports::Http,
-    types::{Block, BlockId, BlockNumber, SyncInfo, Transaction, TransactionId, H160, H256},
+    api::SubscriptionStream,
+    ethabi::{self, ParamType, Token},
+    futures::future::join_all,
+    signing::keccak256,
+    transports::{Http, WebSocket},
+    types::{
+        Block, BlockHeader, BlockId, BlockNumber, Bytes, CallRequest, SyncInfo, Trace,
+        TraceFilterBuilder, Transaction, TransactionId, TransactionReceipt, H160, H256, U256,
+    },
     Web3,
 };
 
-use crate::types::MonError;
+use crate::{helpers::redact_url, types::MonError};
+
+// How long a request can take before its endpoint is considered down
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// An endpoint this many blocks behind the best one is considered out of sync
+const MAX_BLOCK_LAG: u64 = 10;
+// The blocks over which the average block time is measured to guess where a window starts
+const BLOCK_TIME_SAMPLE: u64 = 1000;
+// The JSON-RPC error code of a method the node doesn't serve
+const METHOD_NOT_FOUND: i64 = -32601;
 
 #[derive(Debug, Clone)]
-pub struct RpcClient {
+struct Endpoint {
+    url: String,
     client: Web3<Http>,
-    pub rpc_url: String,
+}
+
+// The requests go to the active endpoint and fail over to the next ones in priority order
+// when it can't be reached, the clones share the active endpoint
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    // In priority order, the first one is preferred
+    endpoints: Arc<Vec<Endpoint>>,
+    active: Arc<AtomicUsize>,
+    // Pushes the new blocks when it's set, everything else still goes over HTTP
+    pub ws_url: Option<String>,
+}
+
+// The URLs are redacted, so that the config can be compared and logged
+impl Serialize for RpcClient {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut client = serializer.serialize_struct("RpcClient", 2)?;
+        client.serialize_field(
+            "rpc_urls",
+            &self
+                .rpc_urls()
+                .into_iter()
+                .map(redact_url)
+                .collect::<Vec<String>>(),
+        )?;
+        client.serialize_field("ws_url", &self.ws_url.as_deref().map(redact_url))?;
+        client.end()
+    }
 }
 
 impl RpcClient {
-    pub fn new(rpc_url: String) -> Result<RpcClient, MonError> {
-        let transport = web3::transports::Http::new(rpc_url.as_str())
-            .context("Unable to get web3 transport!")
-            .map_err(|e| MonError::RpcClientError(e))?;
-        let web3 = web3::Web3::new(transport);
+    pub fn new(rpc_urls: Vec<String>, ws_url: Option<String>) -> Result<RpcClient, MonError> {
+        if rpc_urls.is_empty() {
+            return Err(MonError::InvalidArgument(String::from(
+                "At least one RPC URL is needed",
+            )));
+        }
+
+        let endpoints = rpc_urls
+            .into_iter()
+            .map(|url| {
+                let transport = web3::transports::Http::new(url.as_str())
+                    .context(format!("Unable to get web3 transport for {url}!"))
+                    .map_err(MonError::RpcClientError)?;
+                Ok(Endpoint {
+                    url,
+                    client: web3::Web3::new(transport),
+                })
+            })
+            .collect::<Result<Vec<Endpoint>, MonError>>()?;
 
         Ok(RpcClient {
-            client: web3,
-            rpc_url,
+            endpoints: Arc::new(endpoints),
+            active: Arc::new(AtomicUsize::new(0)),
+            ws_url,
         })
     }
 
+    // All the RPC URLs in priority order, they may hold an API key so don't log them
+    pub fn rpc_urls(&self) -> Vec<&str> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.url.as_str())
+            .collect()
+    }
+
+    // The endpoint `index` for the logs, its URL may hold an API key so only its host is kept
+    fn endpoint_name(&self, index: usize) -> String {
+        format!("{} ({})", index + 1, redact_url(&self.endpoints[index].url))
+    }
+
+    // The index of the endpoint the requests go to, 0 is the preferred one
+    pub fn active_endpoint(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    // Send `request` to the active endpoint, and to the other ones in priority order
+    // until one of them can be reached, which then becomes the active one
+    // Errors returned by a node which answered (e.g. a reverted call) don't fail over
+    async fn request<R, F, Fut>(&self, request: F) -> Result<R, web3::Error>
+    where
+        F: Fn(Web3<Http>) -> Fut,
+        Fut: Future<Output = Result<R, web3::Error>>,
+    {
+        let active = self.active_endpoint();
+        let mut last_err = web3::Error::Unreachable;
+        for index in
+            std::iter::once(active).chain((0..self.endpoints.len()).filter(|i| *i != active))
+        {
+            let endpoint = &self.endpoints[index];
+            let result = tokio::time::timeout(REQUEST_TIMEOUT, request(endpoint.client.clone()))
+                .await
+                .unwrap_or(Err(web3::Error::Unreachable));
+
+            match result {
+                Err(err) if is_unreachable(&err) => {
+                    warn!("RPC endpoint {} failed: {err}", self.endpoint_name(index));
+                    last_err = err;
+                }
+                result => {
+                    // Another request may have failed over already
+                    if index != active
+                        && self
+                            .active
+                            .compare_exchange(active, index, Ordering::Relaxed, Ordering::Relaxed)
+                            .is_ok()
+                    {
+                        warn!("Failing over to RPC endpoint {}", self.endpoint_name(index));
+                    }
+                    return result;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
+    // Probe every endpoint and make the most preferred one which answers and keeps up with
+    // the best block among them the active one
+    // This fails over from an active endpoint which answers but fell behind, which the requests
+    // can't tell, and switches back once a preferred endpoint is healthy again
+    pub async fn check_endpoints(&self) {
+        let blocks =
+            join_all((0..self.endpoints.len()).map(|index| self.block_number_of(index))).await;
+        for (index, block) in blocks.iter().enumerate() {
+            match block {
+                Ok(block) => debug!(
+                    "RPC endpoint {} is at block {block}",
+                    self.endpoint_name(index)
+                ),
+                Err(err) => debug!("RPC endpoint {} is down: {err}", self.endpoint_name(index)),
+            }
+        }
+
+        let Some(best_block) = blocks.iter().flatten().max().copied() else {
+            warn!("None of the RPC endpoints answered");
+            return;
+        };
+        let Some(healthy) = blocks.iter().position(|block| {
+            block
+                .as_ref()
+                .is_ok_and(|block| block + MAX_BLOCK_LAG >= best_block)
+        }) else {
+            return;
+        };
+
+        let active = self.active_endpoint();
+        if healthy == active {
+            return;
+        }
+        if self
+            .active
+            .compare_exchange(active, healthy, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+        let switch = format!(
+            "Switching from RPC endpoint {} to {}",
+            self.endpoint_name(active),
+            self.endpoint_name(healthy)
+        );
+        match &blocks[active] {
+            Ok(block) if block + MAX_BLOCK_LAG < best_block => {
+                warn!("{switch} because the active one is behind at block {block} of {best_block}")
+            }
+            Ok(_) => info!("{switch}, which is preferred and healthy again"),
+            Err(err) => warn!("{switch} because the active one failed: {err}"),
+        }
+    }
+
+    async fn block_number_of(&self, index: usize) -> Result<u64, web3::Error> {
+        let block_number = self.endpoints[index].client.eth().block_number();
+        let block_number = tokio::time::timeout(REQUEST_TIMEOUT, block_number)
+            .await
+            .unwrap_or(Err(web3::Error::Unreachable))?;
+
+        Ok(block_number.as_u64())
+    }
+
+    // Subscribe to the headers of the new blocks over the WebSocket URL
+    // The connection is kept open for as long as the subscription
+    pub async fn subscribe_new_heads(
+        &self,
+    ) -> Result<SubscriptionStream<WebSocket, BlockHeader>, MonError> {
+        let ws_url = self
+            .ws_url
+            .as_deref()
+            .context("No WebSocket URL to subscribe to")
+            .map_err(MonError::RpcClientError)?;
+        let transport = WebSocket::new(ws_url)
+            .await
+            .context(format!("Couldn't connect to {}!", redact_url(ws_url)))
+            .map_err(MonError::RpcClientError)?;
+
+        Ok(Web3::new(transport)
+            .eth_subscribe()
+            .subscribe_new_heads()
+            .await?)
+    }
+
     pub async fn latest_block(&self) -> Result<Option<Block<H256>>, MonError> {
         Ok(self
-            .client
-            .eth()
-            .block(self.client.eth().block_number().await?.into())
+            .request(|client| async move {
+                let block_number = client.eth().block_number().await?;
+                client.eth().block(block_number.into()).await
+            })
             .await?)
     }
 
     pub async fn latest_block_id(&self) -> Result<u64, MonError> {
-        Ok(self.client.eth().block_number().await?.as_u64())
+        Ok(self
+            .request(|client| async move { client.eth().block_number().await })
+            .await?
+            .as_u64())
+    }
+
+    pub async fn get_block_timestamp(&self, block_id: u64) -> Result<u64, MonError> {
+        let block = self
+            .request(|client| async move {
+                client
+                    .eth()
+                    .block(BlockId::Number(BlockNumber::Number(block_id.into())))
+                    .await
+            })
+            .await?
+            .context(format!("Block {block_id} not found"))
+            .map_err(MonError::RpcClientError)?;
+
+        Ok(block.timestamp.as_u64())
+    }
+
+    // The first block in `from..=to` with a timestamp of at least `timestamp`, or `to` if there's none
+    pub async fn first_block_since(
+        &self,
+        timestamp: u64,
+        from: u64,
+        to: u64,
+    ) -> Result<u64, MonError> {
+        let (mut low, mut high) = (from, to);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.get_block_timestamp(middle).await? < timestamp {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        Ok(low)
+    }
+
+    // A block in `..=to` (whose timestamp is `to_timestamp`) with a timestamp before `timestamp`
+    // or block 0, close enough to it
+    // to bound `first_block_since` when there's no previous window start
+    // The average block time of the last blocks gives a first guess,
+    // which goes back twice as far each time it's still too late
+    pub async fn block_before(
+        &self,
+        timestamp: u64,
+        to: u64,
+        to_timestamp: u64,
+    ) -> Result<u64, MonError> {
+        if to_timestamp < timestamp {
+            return Ok(to);
+        }
+
+        let sample_start = to.saturating_sub(BLOCK_TIME_SAMPLE);
+        let sample_blocks = to - sample_start;
+        let sample_seconds =
+            to_timestamp.saturating_sub(self.get_block_timestamp(sample_start).await?);
+        let mut blocks_back = match sample_seconds {
+            0 => sample_blocks,
+            _ => (to_timestamp - timestamp).saturating_mul(sample_blocks) / sample_seconds,
+        };
+        // A bit further than the guess, so that a block time which slowed down doesn't take a second try
+        blocks_back = blocks_back.saturating_add(blocks_back / 8).max(1);
+
+        let mut guess = to.saturating_sub(blocks_back);
+        while guess > 0 && self.get_block_timestamp(guess).await? >= timestamp {
+            blocks_back = blocks_back.saturating_mul(2);
+            guess = to.saturating_sub(blocks_back);
+        }
+
+        Ok(guess)
+    }
+
+    // The hash of the canonical block `block_id`
+    pub async fn get_block_hash(&self, block_id: u64) -> Result<Option<H256>, MonError> {
+        Ok(self
+            .request(|client| async move {
+                client
+                    .eth()
+                    .block(BlockId::Number(BlockNumber::Number(block_id.into())))
+                    .await
+            })
+            .await?
+            .and_then(|block| block.hash))
+    }
+
+    // The block with the hashes of its txs only
+    pub async fn get_block_header(&self, block_id: u64) -> Result<Option<Block<H256>>, MonError> {
+        Ok(self
+            .request(|client| async move {
+                client
+                    .eth()
+                    .block(BlockId::Number(BlockNumber::Number(block_id.into())))
+                    .await
+            })
+            .await?)
     }
 
     pub async fn get_block(&self, block_id: u64) -> Result<Option<Block<Transaction>>, MonError> {
         Ok(self
-            .client
-            .eth()
-            .block_with_txs(BlockId::Number(BlockNumber::Number(block_id.into())))
+            .request(|client| async move {
+                client
+                    .eth()
+                    .block_with_txs(BlockId::Number(BlockNumber::Number(block_id.into())))
+                    .await
+            })
             .await?)
     }
 
     pub async fn current_block(&self) -> Result<u64, MonError> {
         Ok(self
-            .client
-            .eth()
-            .block_number()
+            .request(|client| async move { client.eth().block_number().await })
             .await
             .context(format!(
-                "Couldn't get current block number from {}!",
-                self.rpc_url
+                "Couldn't get current block number from RPC endpoint {}!",
+                self.endpoint_name(self.active_endpoint())
             ))
-            .map_err(|e| MonError::RpcClientError(e))?
+            .map_err(MonError::RpcClientError)?
             .to_string()
             .parse()
             .unwrap_or(0))
@@ -66,12 +382,13 @@ impl RpcClient {
 
     pub async fn syncing_info(&self) -> Result<Option<SyncInfo>, MonError> {
         let res = self
-            .client
-            .eth()
-            .syncing()
+            .request(|client| async move { client.eth().syncing().await })
             .await
-            .context(format!("Couldn't get syncing info for {}!", self.rpc_url))
-            .map_err(|e| MonError::RpcClientError(e))?;
+            .context(format!(
+                "Couldn't get syncing info for RPC endpoint {}!",
+                self.endpoint_name(self.active_endpoint())
+            ))
+            .map_err(MonError::RpcClientError)?;
 
         match res {
             web3::types::SyncState::Syncing(sync_info) => Ok(Some(sync_info)),
@@ -79,21 +396,130 @@ impl RpcClient {
         }
     }
 
+    pub async fn get_receipt(&self, tx_hash: H256) -> Result<Option<TransactionReceipt>, MonError> {
+        Ok(self
+            .request(|client| async move { client.eth().transaction_receipt(tx_hash).await })
+            .await?)
+    }
+
     pub async fn get_tx(&self, tx_id: TransactionId) -> Result<Option<Transaction>, MonError> {
-        Ok(self.client.eth().transaction(tx_id).await?)
+        Ok(self
+            .request(|client| {
+                let tx_id = tx_id.clone();
+                async move { client.eth().transaction(tx_id).await }
+            })
+            .await?)
     }
 
-    pub async fn get_balance(&self, address: String) -> Result<f64, MonError> {
+    // The number of txs sent by `address` up to and including `block_id`
+    pub async fn get_transaction_count(
+        &self,
+        address: String,
+        block_id: u64,
+    ) -> Result<u64, MonError> {
+        let address =
+            H160::from_str(address.as_str()).map_err(|e| MonError::ConversionError(e.into()))?;
         Ok(self
-            .client
-            .eth()
-            .balance(
-                H160::from_str(address.as_str())
-                    .map_err(|e| MonError::ConversionError(e.into()))?,
-                None,
-            )
+            .request(|client| async move {
+                client
+                    .eth()
+                    .transaction_count(address, Some(BlockNumber::Number(block_id.into())))
+                    .await
+            })
+            .await?
+            .as_u64())
+    }
+
+    // The balance of `address` in wei
+    pub async fn get_balance(&self, address: String) -> Result<U256, MonError> {
+        let address =
+            H160::from_str(address.as_str()).map_err(|e| MonError::ConversionError(e.into()))?;
+        self.request(|client| async move { client.eth().balance(address, None).await })
             .await
-            .map_err(|e| MonError::RpcClientError(e.into()))?
-            .as_u64() as f64)
+            .map_err(|e| MonError::RpcClientError(e.into()))
     }
+
+    // Calls made by any of `from_addresses` between `from_block` and `to_block` (inclusive)
+    // None if the node answered that it doesn't have the method, as nodes which don't serve
+    // trace_filter do, any other error may only last this request
+    pub async fn filter_traces(
+        &self,
+        from_addresses: &[String],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Option<Vec<Trace>>, MonError> {
+        let parse = |addresses: &[String]| {
+            addresses
+                .iter()
+                .map(|address| {
+                    H160::from_str(address.as_str())
+                        .map_err(|e| MonError::ConversionError(e.into()))
+                })
+                .collect::<Result<Vec<H160>, MonError>>()
+        };
+
+        let filter = TraceFilterBuilder::default()
+            .from_block(BlockNumber::Number(from_block.into()))
+            .to_block(BlockNumber::Number(to_block.into()))
+            .from_address(parse(from_addresses)?)
+            .build();
+        let traces = self
+            .request(|client| {
+                let filter = filter.clone();
+                async move { client.trace().filter(filter).await }
+            })
+            .await;
+        match traces {
+            Ok(traces) => Ok(Some(traces)),
+            Err(web3::Error::Rpc(err)) if err.code.code() == METHOD_NOT_FOUND => {
+                debug!("trace_filter isn't served: {err}");
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Call a view function (e.g. "isVoterRegistered(address,uint256)") of the contract at `to`
+    // and decode its return values
+    pub async fn call_function(
+        &self,
+        to: &str,
+        signature: &str,
+        args: &[Token],
+        outputs: &[ParamType],
+    ) -> Result<Vec<Token>, MonError> {
+        let mut data = keccak256(signature.as_bytes())[..4].to_vec();
+        data.extend(ethabi::encode(args));
+
+        let request = CallRequest {
+            to: Some(parse_address(to)?),
+            data: Some(Bytes(data)),
+            ..Default::default()
+        };
+        let output = self
+            .request(|client| {
+                let request = request.clone();
+                async move { client.eth().call(request, None).await }
+            })
+            .await?;
+
+        ethabi::decode(outputs, &output.0)
+            .context(format!("Couldn't decode the output of {signature} on {to}"))
+            .map_err(MonError::DeserializationError)
+    }
+}
+
+// The node couldn't be reached or didn't answer with a JSON RPC response
+fn is_unreachable(err: &web3::Error) -> bool {
+    matches!(
+        err,
+        web3::Error::Unreachable
+            | web3::Error::Transport(_)
+            | web3::Error::Io(_)
+            | web3::Error::InvalidResponse(_)
+    )
+}
+
+pub(crate) fn parse_address(address: &str) -> Result<H160, MonError> {
+    H160::from_str(address).map_err(|e| MonError::ConversionError(e.into()))
 }
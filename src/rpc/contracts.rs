@@ -0,0 +1,247 @@
+use web3::ethabi::{ParamType, Token};
+
+use crate::{
+    rpc::{parse_address, RpcClient},
+    types::{MonError, RewardEpochSchedule, SigningDuty, VotingRoundSchedule},
+};
+
+// The FlareContractRegistry is deployed at the same address on every Flare network
+pub const FLARE_CONTRACT_REGISTRY_ADDRESS: &str = "0xaD67FE66660Fb8dFE9d6b1b4240d8650e30F6019";
+
+impl RpcClient {
+    // Look up the address of a Flare system contract (e.g. "VoterRegistry") by its name
+    pub async fn contract_address(&self, registry: &str, name: &str) -> Result<String, MonError> {
+        let output = self
+            .call_function(
+                registry,
+                "getContractAddressByName(string)",
+                &[Token::String(name.to_string())],
+                &[ParamType::Address],
+            )
+            .await?;
+
+        match output.first() {
+            Some(Token::Address(address)) if !address.is_zero() => Ok(format!("{address:?}")),
+            _ => Err(MonError::RpcClientError(anyhow::anyhow!(
+                "{name} is not in the contract registry"
+            ))),
+        }
+    }
+
+    pub async fn current_reward_epoch_id(
+        &self,
+        flare_systems_manager: &str,
+    ) -> Result<u64, MonError> {
+        let output = self
+            .call_function(
+                flare_systems_manager,
+                "getCurrentRewardEpochId()",
+                &[],
+                &[ParamType::Uint(24)],
+            )
+            .await?;
+
+        uint_output(&output, 0, "getCurrentRewardEpochId")
+    }
+
+    pub async fn voting_round_schedule(
+        &self,
+        flare_systems_manager: &str,
+    ) -> Result<VotingRoundSchedule, MonError> {
+        let first_round_start_ts = self
+            .call_function(
+                flare_systems_manager,
+                "firstVotingRoundStartTs()",
+                &[],
+                &[ParamType::Uint(64)],
+            )
+            .await?;
+        let round_duration = self
+            .call_function(
+                flare_systems_manager,
+                "votingEpochDurationSeconds()",
+                &[],
+                &[ParamType::Uint(64)],
+            )
+            .await?;
+
+        Ok(VotingRoundSchedule {
+            first_round_start_ts: uint_output(&first_round_start_ts, 0, "firstVotingRoundStartTs")?,
+            round_duration: nonzero(uint_output(
+                &round_duration,
+                0,
+                "votingEpochDurationSeconds",
+            )?)?,
+        })
+    }
+
+    pub async fn reward_epoch_schedule(
+        &self,
+        flare_systems_manager: &str,
+    ) -> Result<RewardEpochSchedule, MonError> {
+        let first_epoch_start_round = self
+            .call_function(
+                flare_systems_manager,
+                "firstRewardEpochStartVotingRoundId()",
+                &[],
+                &[ParamType::Uint(32)],
+            )
+            .await?;
+        let epoch_duration_rounds = self
+            .call_function(
+                flare_systems_manager,
+                "rewardEpochDurationInVotingEpochs()",
+                &[],
+                &[ParamType::Uint(64)],
+            )
+            .await?;
+
+        Ok(RewardEpochSchedule {
+            first_epoch_start_round: uint_output(
+                &first_epoch_start_round,
+                0,
+                "firstRewardEpochStartVotingRoundId",
+            )?,
+            epoch_duration_rounds: nonzero(uint_output(
+                &epoch_duration_rounds,
+                0,
+                "rewardEpochDurationInVotingEpochs",
+            )?)?,
+        })
+    }
+
+    // When the signing of `duty` for the reward epoch opened, None while it isn't open
+    pub async fn duty_sign_start(
+        &self,
+        flare_systems_manager: &str,
+        duty: SigningDuty,
+        reward_epoch_id: u64,
+    ) -> Result<Option<u64>, MonError> {
+        // Each of them starts with the timestamp and the block the signing opened at
+        let (function, outputs) = match duty {
+            SigningDuty::NewSigningPolicy => ("getSigningPolicySignInfo", 4),
+            SigningDuty::UptimeVote => ("getUptimeVoteSignStartInfo", 2),
+            SigningDuty::Rewards => ("getRewardsSignStartInfo", 2),
+        };
+        let output = self
+            .call_function(
+                flare_systems_manager,
+                &format!("{function}(uint256)"),
+                &[Token::Uint(reward_epoch_id.into())],
+                &vec![ParamType::Uint(64); outputs],
+            )
+            .await?;
+
+        let start_ts = uint_output(&output, 0, function)?;
+        Ok((start_ts != 0).then_some(start_ts))
+    }
+
+    // When `voter` signed `duty` for the reward epoch, None while it didn't
+    pub async fn voter_duty_sign_ts(
+        &self,
+        flare_systems_manager: &str,
+        duty: SigningDuty,
+        reward_epoch_id: u64,
+        voter: &str,
+    ) -> Result<Option<u64>, MonError> {
+        // Each of them returns the timestamp and the block it was signed at
+        let function = match duty {
+            SigningDuty::NewSigningPolicy => "getVoterSigningPolicySignInfo",
+            SigningDuty::UptimeVote => "getVoterUptimeVoteSignInfo",
+            SigningDuty::Rewards => "getVoterRewardsSignInfo",
+        };
+        let output = self
+            .call_function(
+                flare_systems_manager,
+                &format!("{function}(uint256,address)"),
+                &[
+                    Token::Uint(reward_epoch_id.into()),
+                    Token::Address(parse_address(voter)?),
+                ],
+                &[ParamType::Uint(64), ParamType::Uint(64)],
+            )
+            .await?;
+
+        let sign_ts = uint_output(&output, 0, function)?;
+        Ok((sign_ts != 0).then_some(sign_ts))
+    }
+
+    // The vote power block of the reward epoch and whether voters can register for it yet
+    pub async fn voter_registration_data(
+        &self,
+        flare_systems_manager: &str,
+        reward_epoch_id: u64,
+    ) -> Result<(u64, bool), MonError> {
+        let output = self
+            .call_function(
+                flare_systems_manager,
+                "getVoterRegistrationData(uint256)",
+                &[Token::Uint(reward_epoch_id.into())],
+                &[ParamType::Uint(256), ParamType::Bool],
+            )
+            .await?;
+
+        let vote_power_block = uint_output(&output, 0, "getVoterRegistrationData")?;
+        let enabled = bool_output(&output, 1, "getVoterRegistrationData")?;
+
+        Ok((vote_power_block, enabled))
+    }
+
+    pub async fn is_voter_registered(
+        &self,
+        voter_registry: &str,
+        voter: &str,
+        reward_epoch_id: u64,
+    ) -> Result<bool, MonError> {
+        let output = self
+            .call_function(
+                voter_registry,
+                "isVoterRegistered(address,uint256)",
+                &[
+                    Token::Address(parse_address(voter)?),
+                    Token::Uint(reward_epoch_id.into()),
+                ],
+                &[ParamType::Bool],
+            )
+            .await?;
+
+        bool_output(&output, 0, "isVoterRegistered")
+    }
+}
+
+// The `index`th return value of `function` as a u64
+fn uint_output(output: &[Token], index: usize, function: &str) -> Result<u64, MonError> {
+    output
+        .get(index)
+        .cloned()
+        .and_then(Token::into_uint)
+        .and_then(|value| u64::try_from(value).ok())
+        .ok_or_else(|| {
+            MonError::RpcClientError(anyhow::anyhow!(
+                "{function} didn't return a u64 as its output {index}: {output:?}"
+            ))
+        })
+}
+
+// The schedules divide by their durations
+fn nonzero(value: u64) -> Result<u64, MonError> {
+    match value {
+        0 => Err(MonError::RpcClientError(anyhow::anyhow!(
+            "The FlareSystemsManager returned a duration of 0"
+        ))),
+        value => Ok(value),
+    }
+}
+
+// The `index`th return value of `function` as a bool
+fn bool_output(output: &[Token], index: usize, function: &str) -> Result<bool, MonError> {
+    output
+        .get(index)
+        .cloned()
+        .and_then(Token::into_bool)
+        .ok_or_else(|| {
+            MonError::RpcClientError(anyhow::anyhow!(
+                "{function} didn't return a bool as its output {index}: {output:?}"
+            ))
+        })
+}
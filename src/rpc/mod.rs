@@ -1,3 +1,5 @@
 mod client;
+mod contracts;
 
 pub use client::*;
+pub use contracts::*;
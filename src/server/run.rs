@@ -4,12 +4,15 @@ use actix_web::{
     web::{self, Data},
     App, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer,
 };
+use prettytable::{row, Table};
 use prometheus::Encoder;
+use std::time::Duration;
 use tokio::sync::watch;
-use tracing::info;
+use tracing::{error, info};
+use web3::types::U256;
 
 use crate::{
-    rpc::RpcClient,
+    helpers::format_flr,
     types::{MonError, RuntimeConfig},
     Metrics,
 };
@@ -17,25 +20,26 @@ use crate::{
 pub fn run(
     listen_addr: String,
     port: u16,
-    rpc_url: String,
-    config: RuntimeConfig,
+    shutdown_timeout: Duration,
+    config: watch::Receiver<RuntimeConfig>,
     receiver: watch::Receiver<crate::types::Data>,
 ) -> Result<Server, MonError> {
     let metrics = Metrics::new();
     metrics.register()?;
 
-    let rpc_client = RpcClient::new(rpc_url)?;
-
     let server = HttpServer::new(move || {
         App::new()
             .route("/", web::get().to(health_check))
             .route("/metrics", web::get().to(get_metrics))
-            .app_data(web::Data::new(rpc_client.clone()))
+            .route("/gas-costs", web::get().to(get_gas_costs))
             .app_data(web::Data::new(metrics.clone()))
             .app_data(web::Data::new(config.clone()))
             .app_data(web::Data::new(receiver.clone()))
     })
     .bind((listen_addr, port))?
+    // The signals are handled with the monitor
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout.as_secs())
     .run();
 
     Ok(server)
@@ -43,22 +47,26 @@ pub fn run(
 
 async fn get_metrics(
     req: HttpRequest,
-    rpc_client: Data<RpcClient>,
     metrics: Data<Metrics>,
-    config: Data<RuntimeConfig>,
+    config: Data<watch::Receiver<RuntimeConfig>>,
     receiver: Data<watch::Receiver<crate::types::Data>>,
 ) -> Result<HttpResponse, MonError> {
     info!("Request to: {}", req.head().uri);
 
-    // Update from rpc
-    metrics.update_for_rpc(&rpc_client.into_inner()).await?;
+    // The config can be reloaded at any time
+    let config = config.borrow().clone();
+
+    // Update from rpc, the metrics from the monitor are still served while it's down
+    if let Err(err) = metrics.update_for_rpc(&config.rpc_client).await {
+        error!("Couldn't update the metrics from the RPC: {err}");
+    }
 
     // Update for the data from monitoring
     let data = receiver.borrow().clone();
     metrics.update_for_monitoring_data(&data).await?;
 
     let (encoder, mut buffer) = metrics.get_encoder_and_buffer()?;
-    let block_window_metric = format!("\n# HELP ftso_search_window The ftso block search window\n# TYPE ftso_search_window gauge\nftso_search_window {}\n", config.block_window as f32).as_bytes().to_vec();
+    let block_window_metric = format!("\n# HELP ftso_search_window The number of blocks in the last search window\n# TYPE ftso_search_window gauge\nftso_search_window {}\n", receiver.borrow().window_blocks as f32).as_bytes().to_vec();
 
     buffer.extend(&block_window_metric);
 
@@ -67,6 +75,54 @@ async fn get_metrics(
         .body(buffer))
 }
 
+// The FLR spent on gas by each address of each identity per reward epoch, as a plain text table
+async fn get_gas_costs(
+    req: HttpRequest,
+    config: Data<watch::Receiver<RuntimeConfig>>,
+    receiver: Data<watch::Receiver<crate::types::Data>>,
+) -> HttpResponse {
+    info!("Request to: {}", req.head().uri);
+
+    let config = config.borrow().clone();
+
+    let gas_costs = receiver.borrow().gas_costs.clone();
+    let mut table = Table::new();
+    table.set_titles(row![
+        "Identity",
+        "Reward epoch",
+        "Signing Policy Address",
+        "Submit Address",
+        "Submit Signature Address",
+        "Total"
+    ]);
+    for identity in &config.identities {
+        for reward_epoch_id in gas_costs.reward_epoch_ids() {
+            let costs = [
+                &identity.signing_policy_address,
+                &identity.submit_address,
+                &identity.submit_signature_address,
+            ]
+            .map(|address| gas_costs.reward_epoch(reward_epoch_id, address));
+            let total = costs
+                .iter()
+                .fold(U256::zero(), |total, cost| total.saturating_add(*cost));
+
+            table.add_row(row![
+                identity.name,
+                reward_epoch_id,
+                format_flr(costs[0]),
+                format_flr(costs[1]),
+                format_flr(costs[2]),
+                format_flr(total)
+            ]);
+        }
+    }
+
+    HttpResponse::Ok()
+        .insert_header(("Content-Type", "text/plain; charset=utf-8"))
+        .body(table.to_string())
+}
+
 async fn health_check(req: HttpRequest) -> HttpResponse {
     info!("Request to: {}", req.head().uri);
     HttpResponse::Ok().finish()
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use web3::types::U256;
+
+use crate::helpers::wei_to_flr;
+
+// How fast an address is draining, in FLR per hour, and how long its balance lasts at that pace
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Runway {
+    pub burn_rate: Option<f64>,
+    pub hours_until_empty: Option<f64>,
+}
+
+impl Runway {
+    pub fn below(&self, hours: u64) -> bool {
+        self.hours_until_empty
+            .is_some_and(|hours_until_empty| hours_until_empty < hours as f64)
+    }
+}
+
+// The balances of an address sampled by the monitor, as (timestamp, wei)
+#[derive(Debug, Clone, Default)]
+pub struct BalanceHistory {
+    samples: VecDeque<(u64, U256)>,
+}
+
+impl BalanceHistory {
+    // Record a sample and only keep the ones of the last `window` seconds
+    pub fn record(&mut self, timestamp: u64, balance: U256, window: u64) {
+        // The samples from before a top up would hide what was burned since
+        if self.samples.back().is_some_and(|(_, last)| balance > *last) {
+            self.samples.clear();
+        }
+        self.samples.push_back((timestamp, balance));
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|(sampled_at, _)| sampled_at + window < timestamp)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    // The burn rate is only estimated once the samples span at least `min_span` seconds
+    pub fn runway(&self, min_span: u64) -> Runway {
+        let (Some((first_ts, first)), Some((last_ts, last))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return Runway::default();
+        };
+
+        let span = last_ts - first_ts;
+        if span < min_span || span == 0 {
+            return Runway::default();
+        }
+
+        let burn_rate = wei_to_flr(first.saturating_sub(*last)) / (span as f64 / 3600.0);
+        let hours_until_empty = (burn_rate > 0.0).then(|| wei_to_flr(*last) / burn_rate);
+
+        Runway {
+            burn_rate: Some(burn_rate),
+            hours_until_empty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::flr_to_wei;
+
+    fn flr(flr: &str) -> U256 {
+        flr_to_wei(flr).unwrap()
+    }
+
+    #[test]
+    fn runway_at_the_recent_pace() {
+        let mut history = BalanceHistory::default();
+        history.record(0, flr("100"), 7200);
+        // Too short a span to tell
+        assert!(history.runway(600).burn_rate.is_none());
+
+        history.record(1800, flr("95"), 7200);
+        history.record(3600, flr("90"), 7200);
+        let runway = history.runway(600);
+        assert_eq!(runway.burn_rate, Some(10.0));
+        assert_eq!(runway.hours_until_empty, Some(9.0));
+        assert!(runway.below(10));
+        assert!(!runway.below(9));
+    }
+
+    #[test]
+    fn no_burn_never_runs_out() {
+        let mut history = BalanceHistory::default();
+        history.record(0, flr("100"), 7200);
+        history.record(3600, flr("100"), 7200);
+
+        let runway = history.runway(600);
+        assert_eq!(runway.burn_rate, Some(0.0));
+        assert_eq!(runway.hours_until_empty, None);
+        assert!(!runway.below(u64::MAX));
+    }
+
+    #[test]
+    fn a_top_up_restarts_the_history() {
+        let mut history = BalanceHistory::default();
+        history.record(0, flr("100"), 7200);
+        history.record(3600, flr("50"), 7200);
+        history.record(3700, flr("1000"), 7200);
+        assert!(history.runway(600).burn_rate.is_none());
+
+        history.record(7300, flr("999"), 7200);
+        assert_eq!(history.runway(600).burn_rate, Some(1.0));
+    }
+
+    #[test]
+    fn old_samples_leave_the_window() {
+        let mut history = BalanceHistory::default();
+        history.record(0, flr("200"), 3600);
+        history.record(3600, flr("110"), 3600);
+        history.record(7200, flr("100"), 3600);
+
+        // Only the last hour counts
+        assert_eq!(history.runway(600).burn_rate, Some(10.0));
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+use web3::types::U256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BalanceLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl Display for BalanceLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BalanceLevel::Ok => write!(f, "ok"),
+            BalanceLevel::Warning => write!(f, "warning"),
+            BalanceLevel::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+// The balances (in wei) under which an address is low on funds
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BalanceThresholds {
+    pub warning: Option<U256>,
+    pub critical: Option<U256>,
+}
+
+impl BalanceThresholds {
+    pub fn level(&self, balance: U256) -> BalanceLevel {
+        if self.critical.is_some_and(|critical| balance < critical) {
+            BalanceLevel::Critical
+        } else if self.warning.is_some_and(|warning| balance < warning) {
+            BalanceLevel::Warning
+        } else {
+            BalanceLevel::Ok
+        }
+    }
+
+    pub fn threshold(&self, level: BalanceLevel) -> Option<U256> {
+        match level {
+            BalanceLevel::Ok => None,
+            BalanceLevel::Warning => self.warning,
+            BalanceLevel::Critical => self.critical,
+        }
+    }
+}
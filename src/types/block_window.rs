@@ -0,0 +1,83 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    helpers::{format_duration, parse_duration},
+    types::MonError,
+};
+
+// How far back from the latest block the monitor looks
+// A duration is translated to blocks with the block timestamps, so the window
+// stays the same if the block time changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BlockWindow {
+    Blocks(u64),
+    Duration(Duration),
+}
+
+impl FromStr for BlockWindow {
+    type Err = MonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(blocks) = s.parse::<u64>() {
+            return Ok(BlockWindow::Blocks(blocks));
+        }
+
+        let duration = parse_duration(s)?;
+        if duration.as_secs() == 0 {
+            return Err(MonError::InvalidArgument(format!(
+                "The block window {s} is shorter than a second"
+            )));
+        }
+        Ok(BlockWindow::Duration(duration))
+    }
+}
+
+impl Display for BlockWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockWindow::Blocks(blocks) => write!(f, "{blocks} blocks"),
+            BlockWindow::Duration(duration) => write!(f, "{}", format_duration(*duration)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "100".parse::<BlockWindow>().unwrap(),
+            BlockWindow::Blocks(100)
+        );
+        assert_eq!(
+            "180s".parse::<BlockWindow>().unwrap(),
+            BlockWindow::Duration(Duration::from_secs(180))
+        );
+        assert_eq!(
+            "1500ms".parse::<BlockWindow>().unwrap(),
+            BlockWindow::Duration(Duration::from_millis(1_500))
+        );
+        // A zero duration would be an empty window
+        assert!("0s".parse::<BlockWindow>().is_err());
+        assert!("999ms".parse::<BlockWindow>().is_err());
+        assert!("3 blocks".parse::<BlockWindow>().is_err());
+    }
+
+    #[test]
+    fn display_keeps_the_duration() {
+        assert_eq!(BlockWindow::Blocks(100).to_string(), "100 blocks");
+        assert_eq!(
+            BlockWindow::Duration(Duration::from_millis(1_500)).to_string(),
+            "1500ms"
+        );
+        assert_eq!(
+            BlockWindow::Duration(Duration::from_secs(180)).to_string(),
+            "3m"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    num::{NonZeroU16, NonZeroU64},
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{
+    helpers::parse_duration,
+    types::{AddressRole, AlertDelay, BlockWindow, DetectionMode, Hysteresis, MonError},
+};
+
+// The settings read from the --config TOML file
+// The keys are named after the flags, every flag given on the command line
+// takes precedence over the file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub metrics_port: Option<u16>,
+    pub metrics_addr: Option<String>,
+    pub submission_address: Option<String>,
+    pub contract_registry_address: Option<String>,
+    pub first_voting_round_start_ts: Option<u64>,
+    pub voting_round_duration: Option<NonZeroU64>,
+    pub first_reward_epoch_start_round: Option<u64>,
+    pub reward_epoch_duration_rounds: Option<NonZeroU64>,
+    pub uptime_vote_deadline: Option<u64>,
+    pub rewards_deadline: Option<u64>,
+    pub signing_policy_deadline: Option<u64>,
+    pub duty_deadline_warning: Option<u64>,
+    pub rpc: RpcSection,
+    pub alerting: AlertingSection,
+    pub identities: Vec<IdentitySection>,
+}
+
+// [rpc]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RpcSection {
+    // One URL or a list of them in priority order
+    #[serde(deserialize_with = "urls")]
+    pub url: Option<Vec<String>>,
+    pub ws_url: Option<String>,
+    #[serde(deserialize_with = "block_window")]
+    pub block_window: Option<BlockWindow>,
+    #[serde(deserialize_with = "duration")]
+    pub poll_interval: Option<Duration>,
+    pub fetch_concurrency: Option<NonZeroU16>,
+    #[serde(deserialize_with = "detection_mode")]
+    pub detection_mode: Option<DetectionMode>,
+}
+
+// [alerting]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AlertingSection {
+    pub tg_api_key: Option<String>,
+    #[serde(deserialize_with = "chat_id")]
+    pub tg_chat_id: Option<String>,
+    pub repeat_interval: Option<NonZeroU64>,
+    pub burn_rate_window: Option<NonZeroU64>,
+    pub runway_warning_hours: Option<u64>,
+    pub balance_thresholds: BalanceThresholdsSection,
+    // [alerting.hysteresis], by alert condition
+    #[serde(deserialize_with = "hysteresis")]
+    pub hysteresis: BTreeMap<String, Hysteresis>,
+}
+
+// [alerting.balance-thresholds], each identity can override them
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BalanceThresholdsSection {
+    pub submit_warning: Option<FlrAmount>,
+    pub submit_critical: Option<FlrAmount>,
+    pub submit_signature_warning: Option<FlrAmount>,
+    pub submit_signature_critical: Option<FlrAmount>,
+    pub signing_policy_warning: Option<FlrAmount>,
+    pub signing_policy_critical: Option<FlrAmount>,
+}
+
+// [[identities]]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct IdentitySection {
+    pub name: String,
+    pub submit_address: String,
+    pub submit_signature_address: String,
+    pub signing_policy_address: String,
+    pub identity_address: Option<String>,
+    #[serde(default)]
+    pub balance_thresholds: BalanceThresholdsSection,
+}
+
+// An amount of FLR, written either as a string or as a number
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FlrAmount {
+    Text(String),
+    Integer(u64),
+    Float(f64),
+}
+
+impl Display for FlrAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlrAmount::Text(amount) => write!(f, "{amount}"),
+            FlrAmount::Integer(amount) => write!(f, "{amount}"),
+            FlrAmount::Float(amount) => write!(f, "{amount}"),
+        }
+    }
+}
+
+impl BalanceThresholdsSection {
+    // The warning and critical amounts of `role`
+    pub fn amounts(&self, role: AddressRole) -> (Option<&FlrAmount>, Option<&FlrAmount>) {
+        match role {
+            AddressRole::Submit => (self.submit_warning.as_ref(), self.submit_critical.as_ref()),
+            AddressRole::SubmitSignature => (
+                self.submit_signature_warning.as_ref(),
+                self.submit_signature_critical.as_ref(),
+            ),
+            AddressRole::SigningPolicy => (
+                self.signing_policy_warning.as_ref(),
+                self.signing_policy_critical.as_ref(),
+            ),
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn load(path: &str) -> Result<ConfigFile, MonError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| MonError::ConfigError(format!("Can't read {path}: {err}")))?;
+
+        toml::from_str(&content).map_err(|err| MonError::ConfigError(format!("{path}: {err}")))
+    }
+}
+
+// A number or a string, for the settings which take both
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(u64),
+    Text(String),
+}
+
+// One string or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+fn urls<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(url) => Ok(Some(vec![url])),
+        OneOrMany::Many(urls) => Ok(Some(urls)),
+    }
+}
+
+// A number of blocks or a duration like "180s"
+fn block_window<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<BlockWindow>, D::Error> {
+    match NumberOrText::deserialize(deserializer)? {
+        NumberOrText::Number(blocks) => Ok(Some(BlockWindow::Blocks(blocks))),
+        NumberOrText::Text(window) => window.parse().map(Some).map_err(D::Error::custom),
+    }
+}
+
+// One of the values of --detection-mode
+fn detection_mode<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DetectionMode>, D::Error> {
+    let mode = String::deserialize(deserializer)?;
+    <DetectionMode as ValueEnum>::from_str(&mode, false)
+        .map(Some)
+        .map_err(D::Error::custom)
+}
+
+// A Telegram chat id, which is a (negative for groups) number or a @channel name
+fn chat_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ChatId {
+        Number(i64),
+        Text(String),
+    }
+
+    Ok(Some(match ChatId::deserialize(deserializer)? {
+        ChatId::Number(chat_id) => chat_id.to_string(),
+        ChatId::Text(chat_id) => chat_id,
+    }))
+}
+
+// Each condition takes a number of voting rounds or "fire_after[,resolve_after]"
+fn hysteresis<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Hysteresis>, D::Error> {
+    BTreeMap::<String, NumberOrText>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(condition, hysteresis)| {
+            let hysteresis = match hysteresis {
+                NumberOrText::Number(rounds) => Ok(Hysteresis {
+                    fire_after: AlertDelay::Rounds(rounds),
+                    resolve_after: AlertDelay::Rounds(rounds),
+                }),
+                NumberOrText::Text(hysteresis) => hysteresis.parse().map_err(D::Error::custom),
+            };
+            hysteresis.map(|hysteresis| (condition, hysteresis))
+        })
+        .collect()
+}
+
+// A duration like "180s" or a number of seconds
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    match NumberOrText::deserialize(deserializer)? {
+        NumberOrText::Number(seconds) => Ok(Some(Duration::from_secs(seconds))),
+        NumberOrText::Text(duration) => parse_duration(&duration)
+            .map(Some)
+            .map_err(D::Error::custom),
+    }
+}
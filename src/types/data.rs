@@ -1,11 +1,71 @@
-#[derive(Debug, Clone)]
+use std::collections::BTreeMap;
+
+use web3::types::U256;
+
+use crate::types::{DutyStatus, GasCostSummary, GasCosts, Runway, SigningDuty, SubmissionStats};
+
+#[derive(Debug, Clone, Default)]
 pub struct Data {
+    pub scan_duration: f64,
+    // The number of blocks in the last window
+    pub window_blocks: u64,
+
+    // The last voting round checked within the window
+    pub voting_round_id: u64,
+    pub reward_epoch_id: u64,
+    // The timestamp of the latest block, which the duties were checked against
+    pub block_timestamp: u64,
+
+    // Only scanning blocks fetches the receipts the gas costs are taken from
+    pub gas_costs: GasCosts,
+
+    pub identities: Vec<IdentityData>,
+
+    // The loops of the monitor which failed since the start and
+    // whether the last one succeeded
+    pub monitor_errors: u64,
+    pub monitor_healthy: bool,
+    // The chain reorganisations found by the block scanner since the start
+    pub reorgs: u64,
+}
+
+// What the monitor found about one identity
+#[derive(Debug, Clone, Default)]
+pub struct IdentityData {
+    pub name: String,
+
     pub signing_policy_tx_found: bool,
-    pub signing_policy_balance: f64,
+    pub signing_policy_tx_successful: u64,
+    pub signing_policy_tx_reverted: u64,
+    pub signing_policy_balance: U256,
+    pub signing_policy_runway: Runway,
+    pub signing_policy_gas_cost: GasCostSummary,
 
     pub submit_tx_found: bool,
-    pub submit_balance: f64,
+    pub submit_tx_successful: u64,
+    pub submit_tx_reverted: u64,
+    pub submit_balance: U256,
+    pub submit_runway: Runway,
+    pub submit_gas_cost: GasCostSummary,
 
     pub submit_signature_tx_found: bool,
-    pub submit_signature_balance: f64,
+    pub submit_signature_tx_successful: u64,
+    pub submit_signature_tx_reverted: u64,
+    pub submit_signature_balance: U256,
+    pub submit_signature_runway: Runway,
+    pub submit_signature_gas_cost: GasCostSummary,
+
+    // How many rounds within the window were missed
+    pub submit_missed_rounds: u64,
+    pub submit_signature_missed_rounds: u64,
+
+    pub submission_stats: SubmissionStats,
+
+    // The signing policy duties due right now
+    pub signing_duties: Vec<DutyStatus>,
+    pub signing_duty_last_reward_epochs: BTreeMap<SigningDuty, u64>,
+
+    // None without an identity address or before the registration was read
+    pub registered_for_this_epoch: Option<bool>,
+    pub registered_for_next_epoch: Option<bool>,
 }
@@ -0,0 +1,50 @@
+use std::fmt::Display;
+
+use clap::{builder::PossibleValue, ValueEnum};
+use serde::Serialize;
+
+// How the monitor finds out whether an address sent a tx within the window
+// The names are the values of --detection-mode and of detection-mode in --config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionMode {
+    // Fetch every block and look at the sender of each tx
+    Blocks,
+    // Compare the address nonces at both ends of the new blocks and only fetch
+    // them when one changed
+    Nonce,
+    // Ask the RPC (`trace_filter`) in which blocks the addresses sent a tx and only
+    // fetch those, falling back to the blocks mode if the RPC doesn't serve it
+    Trace,
+}
+
+impl ValueEnum for DetectionMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            DetectionMode::Blocks,
+            DetectionMode::Nonce,
+            DetectionMode::Trace,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            DetectionMode::Blocks => {
+                PossibleValue::new("blocks").help("Fetch every block and look at each tx")
+            }
+            DetectionMode::Nonce => PossibleValue::new("nonce")
+                .help("Only fetch the new blocks when the nonce of an address changed"),
+            DetectionMode::Trace => PossibleValue::new("trace")
+                .help("Pull the calls made by the addresses with trace_filter"),
+        })
+    }
+}
+
+impl Display for DetectionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
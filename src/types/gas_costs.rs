@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use web3::types::U256;
+
+// The wei spent on gas by each tracked address since the monitor started,
+// in total and bucketed per voting round and per reward epoch
+#[derive(Debug, Clone, Default)]
+pub struct GasCosts {
+    total: BTreeMap<String, U256>,
+    voting_rounds: BTreeMap<u64, BTreeMap<String, U256>>,
+    reward_epochs: BTreeMap<u64, BTreeMap<String, U256>>,
+}
+
+impl GasCosts {
+    pub fn record(
+        &mut self,
+        address: &str,
+        voting_round_id: u64,
+        reward_epoch_id: u64,
+        cost: U256,
+    ) {
+        let address = address.to_lowercase();
+        for bucket in [
+            &mut self.total,
+            self.voting_rounds.entry(voting_round_id).or_default(),
+            self.reward_epochs.entry(reward_epoch_id).or_default(),
+        ] {
+            let spent = bucket.entry(address.clone()).or_default();
+            *spent = spent.saturating_add(cost);
+        }
+    }
+
+    // Take back a cost recorded before
+    pub fn unrecord(
+        &mut self,
+        address: &str,
+        voting_round_id: u64,
+        reward_epoch_id: u64,
+        cost: U256,
+    ) {
+        let address = address.to_lowercase();
+        for bucket in [
+            Some(&mut self.total),
+            self.voting_rounds.get_mut(&voting_round_id),
+            self.reward_epochs.get_mut(&reward_epoch_id),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(spent) = bucket.get_mut(&address) {
+                *spent = spent.saturating_sub(cost);
+            }
+        }
+    }
+
+    pub fn total(&self, address: &str) -> U256 {
+        spent(&self.total, address)
+    }
+
+    pub fn voting_round(&self, voting_round_id: u64, address: &str) -> U256 {
+        self.voting_rounds
+            .get(&voting_round_id)
+            .map(|costs| spent(costs, address))
+            .unwrap_or_default()
+    }
+
+    pub fn reward_epoch(&self, reward_epoch_id: u64, address: &str) -> U256 {
+        self.reward_epochs
+            .get(&reward_epoch_id)
+            .map(|costs| spent(costs, address))
+            .unwrap_or_default()
+    }
+
+    // The reward epochs with any gas spent, oldest first
+    pub fn reward_epoch_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.reward_epochs.keys().copied()
+    }
+
+    // Forget the voting rounds before `first_voting_round_id`, the reward epochs are kept
+    pub fn prune_voting_rounds(&mut self, first_voting_round_id: u64) {
+        self.voting_rounds = self.voting_rounds.split_off(&first_voting_round_id);
+    }
+}
+
+fn spent(costs: &BTreeMap<String, U256>, address: &str) -> U256 {
+    costs
+        .get(&address.to_lowercase())
+        .copied()
+        .unwrap_or_default()
+}
+
+// What an address spent on gas, in wei
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GasCostSummary {
+    pub total: U256,
+    pub last_voting_round: U256,
+    pub reward_epoch: U256,
+}
@@ -0,0 +1,189 @@
+use std::{str::FromStr, time::Duration};
+
+use serde::Serialize;
+
+use crate::{helpers::parse_duration, types::MonError};
+
+// A number of voting rounds or a duration, like the block window a number
+// without a unit is counted in voting rounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AlertDelay {
+    Rounds(u64),
+    Duration(Duration),
+}
+
+impl AlertDelay {
+    pub fn duration(&self, round_duration: u64) -> Duration {
+        match self {
+            AlertDelay::Rounds(rounds) => {
+                Duration::from_secs(rounds.saturating_mul(round_duration))
+            }
+            AlertDelay::Duration(duration) => *duration,
+        }
+    }
+}
+
+impl FromStr for AlertDelay {
+    type Err = MonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.parse::<u64>() {
+            Ok(rounds) => Ok(AlertDelay::Rounds(rounds)),
+            Err(_) => parse_duration(s).map(AlertDelay::Duration),
+        }
+    }
+}
+
+// How long a condition has to stay wrong before its alert fires,
+// and right before it resolves
+// The monitor loops once per block, so this is set in time rather than in loops
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Hysteresis {
+    pub fire_after: AlertDelay,
+    pub resolve_after: AlertDelay,
+}
+
+impl Default for Hysteresis {
+    fn default() -> Hysteresis {
+        Hysteresis {
+            fire_after: AlertDelay::Rounds(0),
+            resolve_after: AlertDelay::Rounds(0),
+        }
+    }
+}
+
+// fire_after[,resolve_after], resolving after as long as it took to fire by default
+impl FromStr for Hysteresis {
+    type Err = MonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (fire_after, resolve_after) = match s.split_once(',') {
+            Some((fire_after, resolve_after)) => (fire_after, resolve_after),
+            None => (s, s),
+        };
+
+        Ok(Hysteresis {
+            fire_after: fire_after.parse()?,
+            resolve_after: resolve_after.parse()?,
+        })
+    }
+}
+
+// The hysteresis of each kind of alert
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AlertHysteresis {
+    pub participation: Hysteresis,
+    pub balance: Hysteresis,
+    pub runway: Hysteresis,
+    pub reverts: Hysteresis,
+    pub registration: Hysteresis,
+    pub duty: Hysteresis,
+    pub rpc_fallback: Hysteresis,
+}
+
+impl Default for AlertHysteresis {
+    fn default() -> AlertHysteresis {
+        AlertHysteresis {
+            // A round missed because of a flaky RPC shouldn't page
+            participation: Hysteresis {
+                fire_after: AlertDelay::Rounds(3),
+                resolve_after: AlertDelay::Rounds(3),
+            },
+            balance: Hysteresis::default(),
+            runway: Hysteresis::default(),
+            reverts: Hysteresis::default(),
+            registration: Hysteresis::default(),
+            duty: Hysteresis::default(),
+            rpc_fallback: Hysteresis::default(),
+        }
+    }
+}
+
+impl AlertHysteresis {
+    // The names of the conditions in --hysteresis and in [alerting.hysteresis]
+    pub const CONDITIONS: [&'static str; 7] = [
+        "participation",
+        "balance",
+        "runway",
+        "reverts",
+        "registration",
+        "duty",
+        "rpc-fallback",
+    ];
+
+    pub fn set(&mut self, condition: &str, hysteresis: Hysteresis) -> Result<(), MonError> {
+        let setting = match condition.trim() {
+            "participation" => &mut self.participation,
+            "balance" => &mut self.balance,
+            "runway" => &mut self.runway,
+            "reverts" => &mut self.reverts,
+            "registration" => &mut self.registration,
+            "duty" => &mut self.duty,
+            "rpc-fallback" => &mut self.rpc_fallback,
+            _ => {
+                return Err(MonError::InvalidArgument(format!(
+                    "{condition} isn't one of the alert conditions {}",
+                    Self::CONDITIONS.join(", ")
+                )))
+            }
+        };
+        *setting = hysteresis;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "3".parse::<Hysteresis>().unwrap(),
+            Hysteresis {
+                fire_after: AlertDelay::Rounds(3),
+                resolve_after: AlertDelay::Rounds(3),
+            }
+        );
+        assert_eq!(
+            "5m,30s".parse::<Hysteresis>().unwrap(),
+            Hysteresis {
+                fire_after: AlertDelay::Duration(Duration::from_secs(300)),
+                resolve_after: AlertDelay::Duration(Duration::from_secs(30)),
+            }
+        );
+        assert_eq!(
+            "0s,2".parse::<Hysteresis>().unwrap(),
+            Hysteresis {
+                fire_after: AlertDelay::Duration(Duration::ZERO),
+                resolve_after: AlertDelay::Rounds(2),
+            }
+        );
+        assert!("".parse::<Hysteresis>().is_err());
+        assert!("3,".parse::<Hysteresis>().is_err());
+        assert!("3 loops".parse::<Hysteresis>().is_err());
+    }
+
+    #[test]
+    fn delay_in_rounds_follows_the_round_duration() {
+        assert_eq!(AlertDelay::Rounds(3).duration(90), Duration::from_secs(270));
+        assert_eq!(
+            AlertDelay::Duration(Duration::from_secs(10)).duration(90),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn set_by_condition() {
+        let mut hysteresis = AlertHysteresis::default();
+        hysteresis
+            .set("rpc-fallback", "1m".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            hysteresis.rpc_fallback.fire_after,
+            AlertDelay::Duration(Duration::from_secs(60))
+        );
+        assert!(hysteresis.set("reorg", Hysteresis::default()).is_err());
+    }
+}
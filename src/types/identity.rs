@@ -0,0 +1,259 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{
+    helpers::parse_address,
+    types::{BalanceThresholds, MonError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddressRole {
+    Submit,
+    SubmitSignature,
+    SigningPolicy,
+}
+
+impl AddressRole {
+    pub const ALL: [AddressRole; 3] = [
+        AddressRole::SigningPolicy,
+        AddressRole::Submit,
+        AddressRole::SubmitSignature,
+    ];
+
+    // The prefix of the metrics of this role
+    pub fn metric_prefix(&self) -> &'static str {
+        match self {
+            AddressRole::Submit => "submit",
+            AddressRole::SubmitSignature => "submit_signature",
+            AddressRole::SigningPolicy => "signing_policy",
+        }
+    }
+}
+
+impl Display for AddressRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressRole::Submit => write!(f, "Submit Address"),
+            AddressRole::SubmitSignature => write!(f, "Submit Signature Address"),
+            AddressRole::SigningPolicy => write!(f, "Signing Policy Address"),
+        }
+    }
+}
+
+// One FTSO data provider to monitor
+// The name prefixes its alerts and is the "identity" label of its metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct Identity {
+    pub name: String,
+    pub submit_address: String,
+    pub submit_signature_address: String,
+    pub signing_policy_address: String,
+    pub identity_address: Option<String>,
+    pub submit_balance_thresholds: BalanceThresholds,
+    pub submit_signature_balance_thresholds: BalanceThresholds,
+    pub signing_policy_balance_thresholds: BalanceThresholds,
+}
+
+impl Identity {
+    pub fn address(&self, role: AddressRole) -> &str {
+        match role {
+            AddressRole::Submit => &self.submit_address,
+            AddressRole::SubmitSignature => &self.submit_signature_address,
+            AddressRole::SigningPolicy => &self.signing_policy_address,
+        }
+    }
+
+    pub fn balance_thresholds(&self, role: AddressRole) -> &BalanceThresholds {
+        match role {
+            AddressRole::Submit => &self.submit_balance_thresholds,
+            AddressRole::SubmitSignature => &self.submit_signature_balance_thresholds,
+            AddressRole::SigningPolicy => &self.signing_policy_balance_thresholds,
+        }
+    }
+
+    // The role of `address` if it's one of the addresses of this identity
+    pub fn role(&self, address: &str) -> Option<AddressRole> {
+        AddressRole::ALL
+            .into_iter()
+            .find(|role| self.address(*role).eq_ignore_ascii_case(address))
+    }
+
+    // Refuse the addresses which can't be parsed and keep the other ones
+    // in the lowercase 0x form the addresses of the txs are compared in
+    pub fn normalize_addresses(&mut self) -> Result<(), MonError> {
+        let name = &self.name;
+        let parse =
+            |role: &str, address: &str| parse_address(&format!("The {role} of {name}"), address);
+        self.submit_address = parse("Submit Address", &self.submit_address)?;
+        self.submit_signature_address =
+            parse("Submit Signature Address", &self.submit_signature_address)?;
+        self.signing_policy_address =
+            parse("Signing Policy Address", &self.signing_policy_address)?;
+        self.identity_address = self
+            .identity_address
+            .as_deref()
+            .map(|address| parse("Identity Address", address))
+            .transpose()?;
+        Ok(())
+    }
+
+    // The addresses of this identity in lowercase
+    pub fn addresses(&self) -> Vec<String> {
+        AddressRole::ALL
+            .iter()
+            .map(|role| self.address(*role).to_lowercase())
+            .collect()
+    }
+}
+
+// Parse "name=submit,submit_signature,signing_policy[,identity]"
+// The balance thresholds are left empty
+impl FromStr for Identity {
+    type Err = MonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            MonError::InvalidArgument(format!(
+                "{s} isn't name=submit,submit_signature,signing_policy[,identity]"
+            ))
+        };
+
+        let (name, addresses) = s.split_once('=').ok_or_else(invalid)?;
+        let addresses: Vec<&str> = addresses.split(',').map(str::trim).collect();
+        if name.trim().is_empty() || !(3..=4).contains(&addresses.len()) {
+            return Err(invalid());
+        }
+
+        Ok(Identity {
+            name: name.trim().to_string(),
+            submit_address: addresses[0].to_string(),
+            submit_signature_address: addresses[1].to_string(),
+            signing_policy_address: addresses[2].to_string(),
+            identity_address: addresses.get(3).map(|address| address.to_string()),
+            submit_balance_thresholds: BalanceThresholds::default(),
+            submit_signature_balance_thresholds: BalanceThresholds::default(),
+            signing_policy_balance_thresholds: BalanceThresholds::default(),
+        })
+    }
+}
+
+// Every identity needs its own name, and an address can only have one role in one identity
+// as its txs are counted for that one
+pub fn validate_identities(identities: &[Identity]) -> Result<(), MonError> {
+    let mut names = HashSet::new();
+    let mut addresses = HashMap::new();
+    for identity in identities {
+        if !names.insert(identity.name.as_str()) {
+            return Err(MonError::InvalidArgument(format!(
+                "There are several identities named {}",
+                identity.name
+            )));
+        }
+
+        for role in AddressRole::ALL {
+            let address = identity.address(role).to_lowercase();
+            if let Some((other, other_role)) =
+                addresses.insert(address.clone(), (identity.name.as_str(), role))
+            {
+                return Err(MonError::InvalidArgument(format!(
+                    "{address} is both the {other_role} of {other} and the {role} of {}",
+                    identity.name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        let identity = Identity::from_str(" alice = 0x1, 0x2 ,0x3").unwrap();
+        assert_eq!(identity.name, "alice");
+        assert_eq!(identity.submit_address, "0x1");
+        assert_eq!(identity.submit_signature_address, "0x2");
+        assert_eq!(identity.signing_policy_address, "0x3");
+        assert_eq!(identity.identity_address, None);
+
+        let identity = Identity::from_str("bob=0x1,0x2,0x3,0x4").unwrap();
+        assert_eq!(identity.identity_address.as_deref(), Some("0x4"));
+    }
+
+    #[test]
+    fn from_str_refuses_invalid_identities() {
+        for s in [
+            "alice",
+            "=0x1,0x2,0x3",
+            "alice=0x1,0x2",
+            "alice=0x1,0x2,0x3,0x4,0x5",
+        ] {
+            assert!(Identity::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn role_ignores_the_case() {
+        let identity = Identity::from_str("alice=0xAb,0x2,0x3").unwrap();
+        assert_eq!(identity.role("0xaB"), Some(AddressRole::Submit));
+        assert_eq!(identity.role("0x4"), None);
+    }
+
+    #[test]
+    fn normalize_addresses() {
+        let mut identity = Identity::from_str(
+            "alice=0x000000000000000000000000000000000000000A,000000000000000000000000000000000000000b,0x000000000000000000000000000000000000000c,0x000000000000000000000000000000000000000D",
+        )
+        .unwrap();
+        identity.normalize_addresses().unwrap();
+        assert_eq!(
+            identity.submit_address,
+            "0x000000000000000000000000000000000000000a"
+        );
+        assert_eq!(
+            identity.submit_signature_address,
+            "0x000000000000000000000000000000000000000b"
+        );
+        assert_eq!(
+            identity.identity_address.as_deref(),
+            Some("0x000000000000000000000000000000000000000d")
+        );
+
+        let mut identity = Identity::from_str(
+            "bob=0x000000000000000000000000000000000000000a,0x1,0x000000000000000000000000000000000000000c",
+        )
+        .unwrap();
+        let err = identity.normalize_addresses().unwrap_err();
+        assert!(
+            err.to_string().contains("Submit Signature Address of bob"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn validate_identities_refuses_shared_addresses() {
+        let alice = Identity::from_str("alice=0x1,0x2,0x3").unwrap();
+        let bob = Identity::from_str("bob=0x4,0x5,0x6").unwrap();
+        assert!(validate_identities(&[alice.clone(), bob]).is_ok());
+
+        // Across identities, whatever the case
+        let bob = Identity::from_str("bob=0x4,0x5,0xA").unwrap();
+        let carol = Identity::from_str("carol=0x7,0x8,0xa").unwrap();
+        assert!(validate_identities(&[bob, carol]).is_err());
+
+        // Within one identity
+        let dave = Identity::from_str("dave=0x9,0x9,0xb").unwrap();
+        assert!(validate_identities(&[dave]).is_err());
+
+        let alice_again = Identity::from_str("alice=0xc,0xd,0xe").unwrap();
+        assert!(validate_identities(&[alice, alice_again]).is_err());
+    }
+}
@@ -1,7 +1,35 @@
+mod balance_history;
+mod balance_thresholds;
+mod block_window;
+mod config_file;
+mod data;
+mod detection_mode;
+mod gas_costs;
+mod hysteresis;
+mod identity;
 mod mon_error;
+mod reward_epoch_schedule;
 mod runtime_config;
-mod data;
+mod sightings_window;
+mod signing_duty;
+mod submission_call;
+mod tx_outcomes;
+mod voting_round_schedule;
 
+pub use balance_history::*;
+pub use balance_thresholds::*;
+pub use block_window::*;
+pub use config_file::*;
+pub use data::*;
+pub use detection_mode::*;
+pub use gas_costs::*;
+pub use hysteresis::*;
+pub use identity::*;
 pub use mon_error::*;
+pub use reward_epoch_schedule::*;
 pub use runtime_config::*;
-pub use data::*;
+pub use sightings_window::*;
+pub use signing_duty::*;
+pub use submission_call::*;
+pub use tx_outcomes::*;
+pub use voting_round_schedule::*;
@@ -28,6 +28,12 @@ pub enum MonError {
 
     #[error("Couldn't convert to hex")]
     ConversionError(#[source] anyhow::Error),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Invalid config file: {0}")]
+    ConfigError(String),
 }
 
 impl ResponseError for MonError {
@@ -42,6 +48,8 @@ impl ResponseError for MonError {
             MonError::RpcClientError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             MonError::UnableToUnwrapSyncInfo => StatusCode::INTERNAL_SERVER_ERROR,
             MonError::ConversionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            MonError::InvalidArgument(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            MonError::ConfigError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use crate::types::VotingRoundSchedule;
+
+// Reward epochs are back to back windows of `epoch_duration_rounds` voting rounds
+// starting at the voting round `first_epoch_start_round`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RewardEpochSchedule {
+    pub first_epoch_start_round: u64,
+    pub epoch_duration_rounds: u64,
+}
+
+impl RewardEpochSchedule {
+    pub fn reward_epoch_id(&self, voting_round_id: u64) -> u64 {
+        voting_round_id.saturating_sub(self.first_epoch_start_round) / self.epoch_duration_rounds
+    }
+
+    pub fn start_round(&self, reward_epoch_id: u64) -> u64 {
+        self.first_epoch_start_round + reward_epoch_id * self.epoch_duration_rounds
+    }
+
+    pub fn start_ts(&self, voting_rounds: &VotingRoundSchedule, reward_epoch_id: u64) -> u64 {
+        voting_rounds.round_start(self.start_round(reward_epoch_id))
+    }
+}
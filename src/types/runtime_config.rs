@@ -1,12 +1,179 @@
-use crate::rpc::RpcClient;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
 
-#[derive(Debug, Clone)]
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::{
+    helpers::redact,
+    rpc::RpcClient,
+    types::{
+        AddressRole, AlertHysteresis, BlockWindow, DetectionMode, DutyDeadlines, Identity,
+        RewardEpochSchedule, VotingRoundSchedule,
+    },
+};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct RuntimeConfig {
+    #[serde(serialize_with = "redacted")]
     pub tg_api_key: String,
     pub tg_chat_id: String,
     pub rpc_client: RpcClient,
-    pub block_window: u16,
-    pub submit_address: String,
-    pub submit_signature_address: String,
-    pub signing_policy_address: String,
+    pub block_window: BlockWindow,
+    // The minimum time between the start of two loops of the monitor
+    pub poll_interval: Duration,
+    pub fetch_concurrency: usize,
+    pub detection_mode: DetectionMode,
+    pub voting_rounds: VotingRoundSchedule,
+    pub reward_epochs: RewardEpochSchedule,
+    pub duty_deadlines: DutyDeadlines,
+    // Every identity is checked against the same blocks
+    pub identities: Vec<Identity>,
+    // Seconds of balance history used for the burn rate
+    pub burn_rate_window: u64,
+    pub runway_warning_hours: u64,
+    // Seconds before an alert which keeps firing is sent again
+    pub alert_repeat_interval: u64,
+    pub hysteresis: AlertHysteresis,
+    pub submission_address: Option<String>,
+    pub contract_registry_address: String,
+}
+
+impl RuntimeConfig {
+    // The identity `address` belongs to, with its role
+    // The addresses are unique across the identities, see `validate_identities`
+    pub fn identity_of(&self, address: &str) -> Option<(&Identity, AddressRole)> {
+        self.identities
+            .iter()
+            .find_map(|identity| identity.role(address).map(|role| (identity, role)))
+    }
+
+    // The addresses of all the identities in lowercase
+    pub fn tracked_addresses(&self) -> Vec<String> {
+        self.identities
+            .iter()
+            .flat_map(|identity| identity.addresses())
+            .collect()
+    }
+
+    // What changed from this config to `new`, logged when the config is reloaded
+    // The settings are compared in their serialized form, where the secrets are redacted
+    pub fn changes(&self, new: &RuntimeConfig) -> Vec<String> {
+        let settings = |config: &RuntimeConfig| match serde_json::to_value(config) {
+            Ok(Value::Object(settings)) => settings,
+            _ => Map::new(),
+        };
+        let (old, new) = (settings(self), settings(new));
+
+        let mut changes = vec![];
+        for (setting, old_value) in &old {
+            let new_value = new.get(setting).unwrap_or(&Value::Null);
+            if setting != "identities" {
+                if old_value != new_value {
+                    changes.push(format!("{setting}: {old_value} -> {new_value}"));
+                }
+                continue;
+            }
+
+            // Identities are matched by name, one which was added or removed is null on the other side
+            let by_name = |identities: &Value| -> BTreeMap<String, Value> {
+                identities
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|identity| {
+                        (
+                            identity["name"].as_str().unwrap_or_default().to_string(),
+                            identity.clone(),
+                        )
+                    })
+                    .collect()
+            };
+            let (old_identities, new_identities) = (by_name(old_value), by_name(new_value));
+            let names: BTreeSet<&String> =
+                old_identities.keys().chain(new_identities.keys()).collect();
+            for name in names {
+                let old_identity = old_identities.get(name).unwrap_or(&Value::Null);
+                let new_identity = new_identities.get(name).unwrap_or(&Value::Null);
+                if old_identity != new_identity {
+                    changes.push(format!("identity {name}: {old_identity} -> {new_identity}"));
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+fn redacted<S: Serializer>(secret: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&redact(secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn config(identities: &[&str]) -> RuntimeConfig {
+        RuntimeConfig {
+            tg_api_key: String::from("123:secret"),
+            tg_chat_id: String::from("-100"),
+            rpc_client: RpcClient::new(vec![String::from("https://rpc.example.com/secret")], None)
+                .unwrap(),
+            block_window: BlockWindow::Blocks(100),
+            poll_interval: Duration::from_secs(10),
+            fetch_concurrency: 4,
+            detection_mode: DetectionMode::Blocks,
+            voting_rounds: VotingRoundSchedule {
+                first_round_start_ts: 1658430000,
+                round_duration: 90,
+            },
+            reward_epochs: RewardEpochSchedule {
+                first_epoch_start_round: 0,
+                epoch_duration_rounds: 3360,
+            },
+            duty_deadlines: DutyDeadlines {
+                uptime_vote: 7200,
+                rewards: 7200,
+                signing_policy: 7200,
+                warning: 1800,
+            },
+            identities: identities
+                .iter()
+                .map(|identity| Identity::from_str(identity).unwrap())
+                .collect(),
+            burn_rate_window: 86400,
+            runway_warning_hours: 72,
+            alert_repeat_interval: 3600,
+            hysteresis: AlertHysteresis::default(),
+            submission_address: None,
+            contract_registry_address: String::from("0xaD67FE66660Fb8dFE9d6b1b4240d8650e30F6019"),
+        }
+    }
+
+    const A: &str = "a=0x0000000000000000000000000000000000000001,0x0000000000000000000000000000000000000002,0x0000000000000000000000000000000000000003";
+    const B: &str = "b=0x0000000000000000000000000000000000000004,0x0000000000000000000000000000000000000005,0x0000000000000000000000000000000000000006";
+
+    #[test]
+    fn changes() {
+        let old = config(&[A]);
+        assert!(old.changes(&old.clone()).is_empty());
+
+        let mut new = config(&[B]);
+        new.tg_api_key = String::from("456:other secret");
+        new.fetch_concurrency = 8;
+        let changes = old.changes(&new);
+        assert_eq!(changes.len(), 4, "{changes:?}");
+        assert!(changes.iter().all(|change| !change.contains("secret")));
+        assert!(changes.contains(&String::from("fetch_concurrency: 4 -> 8")));
+        assert!(changes
+            .iter()
+            .any(|change| change.starts_with("identity a: {") && change.ends_with("-> null")));
+        assert!(changes
+            .iter()
+            .any(|change| change.starts_with("identity b: null -> {")));
+    }
 }
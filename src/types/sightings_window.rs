@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, HashSet};
+
+use web3::types::{H256, U256};
+
+use crate::types::{SigningCall, SubmissionCall};
+
+// A tx sent by one of the tracked addresses
+// `call` is set when the tx was a call to the Submission contract
+// and `signing_call` when it was a signing duty that was recorded
+#[derive(Debug, Clone)]
+pub struct Sighting {
+    pub from: String,
+    pub tx_hash: H256,
+    pub call: Option<SubmissionCall>,
+    pub signing_call: Option<SigningCall>,
+    pub reverted: bool,
+}
+
+// A tx of a tracked address whose outcome and gas cost were recorded,
+// Submission call or not
+#[derive(Debug, Clone)]
+pub struct ScannedTx {
+    pub from: String,
+    pub tx_hash: H256,
+    pub reverted: bool,
+    pub cost: U256,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BlockSightings {
+    pub timestamp: u64,
+    pub hash: H256,
+    pub parent_hash: H256,
+    pub sightings: Vec<Sighting>,
+    pub txs: Vec<ScannedTx>,
+}
+
+// Sliding window of the txs sent by the tracked addresses in each block
+// The cursor is the last block that was fetched and processed, so every loop
+// only needs to fetch the blocks that came after it
+#[derive(Debug, Clone, Default)]
+pub struct SightingsWindow {
+    cursor: Option<u64>,
+    blocks: BTreeMap<u64, BlockSightings>,
+}
+
+impl SightingsWindow {
+    pub fn new() -> SightingsWindow {
+        SightingsWindow::default()
+    }
+
+    pub fn cursor(&self) -> Option<u64> {
+        self.cursor
+    }
+
+    // The first block that still has to be fetched for a window starting at `window_start`
+    pub fn next_block(&self, window_start: u64) -> u64 {
+        match self.cursor {
+            Some(cursor) => (cursor + 1).max(window_start),
+            None => window_start,
+        }
+    }
+
+    // Record the txs of the tracked addresses in `block_id` and move the cursor forward
+    pub fn insert(&mut self, block_id: u64, block: BlockSightings) {
+        self.blocks.insert(block_id, block);
+        self.cursor = Some(block_id);
+    }
+
+    // The hash of `block_id` if it's in the window
+    pub fn hash(&self, block_id: u64) -> Option<H256> {
+        self.blocks.get(&block_id).map(|block| block.hash)
+    }
+
+    // The blocks in the window before `block_id` with their hash, newest first
+    pub fn hashes_before(&self, block_id: u64) -> impl Iterator<Item = (u64, H256)> + '_ {
+        self.blocks
+            .range(..block_id)
+            .rev()
+            .map(|(block_id, block)| (*block_id, block.hash))
+    }
+
+    // Drop the blocks from `block_id` on, which were reorged out, and move the cursor back
+    // before them so that they get fetched again
+    // Returns the dropped blocks, to take back what was recorded for them
+    pub fn rollback(&mut self, block_id: u64) -> BTreeMap<u64, BlockSightings> {
+        let dropped = self.blocks.split_off(&block_id);
+        if self.cursor.is_some_and(|cursor| cursor >= block_id) {
+            self.cursor = block_id.checked_sub(1);
+        }
+
+        dropped
+    }
+
+    // Drop all the blocks which fell out of the window
+    pub fn prune(&mut self, window_start: u64) {
+        self.blocks = self.blocks.split_off(&window_start);
+    }
+
+    // All the tracked addresses with a successful tx anywhere in the window
+    pub fn active(&self) -> HashSet<String> {
+        self.sightings()
+            .filter(|sighting| !sighting.reverted)
+            .map(|sighting| sighting.from.clone())
+            .collect()
+    }
+
+    // The txs of `address` which reverted within the window, oldest first
+    pub fn reverted_txs(&self, address: &str) -> Vec<H256> {
+        let address = address.to_lowercase();
+        self.blocks
+            .values()
+            .flat_map(|block| block.txs.iter())
+            .filter(|tx| tx.reverted && tx.from == address)
+            .map(|tx| tx.tx_hash)
+            .collect()
+    }
+
+    pub fn sightings(&self) -> impl Iterator<Item = &Sighting> {
+        self.blocks
+            .values()
+            .flat_map(|block| block.sightings.iter())
+    }
+
+    // The txs of the blocks with a timestamp in `start_ts..end_ts`
+    pub fn sightings_between(&self, start_ts: u64, end_ts: u64) -> impl Iterator<Item = &Sighting> {
+        self.blocks
+            .values()
+            .filter(move |block| block.timestamp >= start_ts && block.timestamp < end_ts)
+            .flat_map(|block| block.sightings.iter())
+    }
+
+    // Where the first block with a timestamp of at least `timestamp` is, as far as the window knows:
+    // after the last block before it, and at the latest the first block since it
+    pub fn bounds_since(&self, timestamp: u64) -> (Option<u64>, Option<u64>) {
+        let after = self
+            .blocks
+            .iter()
+            .rev()
+            .find(|(_, block)| block.timestamp < timestamp)
+            .map(|(block_id, _)| block_id + 1);
+        let at_latest = self
+            .blocks
+            .iter()
+            .find(|(_, block)| block.timestamp >= timestamp)
+            .map(|(block_id, _)| *block_id);
+        (after, at_latest)
+    }
+
+    pub fn first_timestamp(&self) -> Option<u64> {
+        self.blocks.values().next().map(|block| block.timestamp)
+    }
+
+    pub fn last_timestamp(&self) -> Option<u64> {
+        self.blocks
+            .values()
+            .next_back()
+            .map(|block| block.timestamp)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(hash: u64) -> BlockSightings {
+        BlockSightings {
+            timestamp: hash,
+            hash: H256::from_low_u64_be(hash),
+            ..Default::default()
+        }
+    }
+
+    fn window(block_ids: &[u64]) -> SightingsWindow {
+        let mut window = SightingsWindow::new();
+        for block_id in block_ids {
+            window.insert(*block_id, block(*block_id));
+        }
+        window
+    }
+
+    #[test]
+    fn next_block_starts_after_the_cursor() {
+        let window = window(&[10, 11, 12]);
+        assert_eq!(window.next_block(5), 13);
+        // The window moved past the cursor
+        assert_eq!(window.next_block(20), 20);
+        assert_eq!(SightingsWindow::new().next_block(5), 5);
+    }
+
+    #[test]
+    fn rollback_moves_the_cursor_back() {
+        let mut window = window(&[10, 11, 12, 13]);
+        let dropped = window.rollback(12);
+
+        assert_eq!(dropped.keys().copied().collect::<Vec<_>>(), vec![12, 13]);
+        assert_eq!(window.cursor(), Some(11));
+        assert_eq!(window.next_block(10), 12);
+        assert_eq!(window.len(), 2);
+    }
+
+    #[test]
+    fn rollback_deeper_than_the_window() {
+        let mut window = window(&[10, 11, 12]);
+        let dropped = window.rollback(5);
+
+        assert_eq!(dropped.len(), 3);
+        assert!(window.is_empty());
+        assert_eq!(window.cursor(), Some(4));
+        assert_eq!(window.next_block(8), 8);
+    }
+
+    #[test]
+    fn rollback_after_the_cursor_keeps_it() {
+        let mut window = window(&[10, 11]);
+        assert!(window.rollback(12).is_empty());
+        assert_eq!(window.cursor(), Some(11));
+    }
+
+    #[test]
+    fn hashes_before_go_newest_first() {
+        // A sparse window, as filled by the nonce and trace modes
+        let window = window(&[10, 14, 20]);
+        assert_eq!(
+            window.hashes_before(20).collect::<Vec<_>>(),
+            vec![
+                (14, H256::from_low_u64_be(14)),
+                (10, H256::from_low_u64_be(10))
+            ]
+        );
+    }
+
+    #[test]
+    fn bounds_since() {
+        let full = window(&[10, 11, 12]);
+        assert_eq!(full.bounds_since(11), (Some(11), Some(11)));
+        assert_eq!(full.bounds_since(5), (None, Some(10)));
+        assert_eq!(full.bounds_since(20), (Some(13), None));
+
+        // The blocks skipped by a sparse window are unknown
+        let sparse = window(&[10, 14, 20]);
+        assert_eq!(sparse.bounds_since(12), (Some(11), Some(14)));
+    }
+
+    #[test]
+    fn prune_keeps_the_cursor() {
+        let mut window = window(&[10, 11, 12]);
+        window.prune(12);
+        assert_eq!(window.len(), 1);
+        assert_eq!(window.first_timestamp(), Some(12));
+        assert_eq!(window.next_block(12), 13);
+    }
+
+    #[test]
+    fn reverted_txs_leave_with_their_block() {
+        let tx = |hash: u64, from: &str, reverted: bool| ScannedTx {
+            from: from.to_string(),
+            tx_hash: H256::from_low_u64_be(hash),
+            reverted,
+            cost: U256::zero(),
+        };
+        let mut window = window(&[10]);
+        let mut reverted_block = block(11);
+        reverted_block.txs = vec![tx(1, "0xa", true), tx(2, "0xa", false), tx(3, "0xb", true)];
+        window.insert(11, reverted_block);
+
+        assert_eq!(window.reverted_txs("0xA"), [H256::from_low_u64_be(1)]);
+        assert!(window.reverted_txs("0xc").is_empty());
+
+        window.prune(12);
+        assert!(window.reverted_txs("0xa").is_empty());
+    }
+}
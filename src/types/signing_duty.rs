@@ -0,0 +1,292 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use serde::Serialize;
+use web3::signing::keccak256;
+
+// The FlareSystemsManager functions the signing policy address has to call once per reward epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SigningDuty {
+    // Sign the signing policy of the next reward epoch, before it starts
+    NewSigningPolicy,
+    // Sign the uptime vote of the previous reward epoch
+    UptimeVote,
+    // Sign the rewards of the previous reward epoch
+    Rewards,
+}
+
+impl SigningDuty {
+    pub const ALL: [SigningDuty; 3] = [
+        SigningDuty::NewSigningPolicy,
+        SigningDuty::UptimeVote,
+        SigningDuty::Rewards,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SigningDuty::NewSigningPolicy => "signNewSigningPolicy",
+            SigningDuty::UptimeVote => "signUptimeVote",
+            SigningDuty::Rewards => "signRewards",
+        }
+    }
+
+    // All of them take the reward epoch ID as their first argument
+    fn signatures(&self) -> &'static [&'static str] {
+        match self {
+            SigningDuty::NewSigningPolicy => {
+                &["signNewSigningPolicy(uint24,bytes32,(uint8,bytes32,bytes32))"]
+            }
+            SigningDuty::UptimeVote => &["signUptimeVote(uint24,bytes32,(uint8,bytes32,bytes32))"],
+            SigningDuty::Rewards => &[
+                "signRewards(uint24,(uint256,uint256)[],bytes32,(uint8,bytes32,bytes32))",
+                "signRewards(uint24,uint64,bytes32,(uint8,bytes32,bytes32))",
+            ],
+        }
+    }
+
+    pub fn from_selector(selector: &[u8]) -> Option<SigningDuty> {
+        SigningDuty::ALL.into_iter().find(|duty| {
+            duty.signatures()
+                .iter()
+                .any(|signature| keccak256(signature.as_bytes())[..4] == *selector)
+        })
+    }
+}
+
+impl Display for SigningDuty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningCall {
+    pub duty: SigningDuty,
+    // The reward epoch the signature is for
+    pub reward_epoch_id: u64,
+}
+
+impl SigningCall {
+    // Decode the calldata of a tx sent to the FlareSystemsManager
+    // Returns None if it isn't one of the signing duties
+    pub fn decode(input: &[u8]) -> Option<SigningCall> {
+        if input.len() < 4 + 32 {
+            return None;
+        }
+        let duty = SigningDuty::from_selector(&input[..4])?;
+
+        // The reward epoch ID is an uint24, so only the last bytes of the first word matter
+        let mut reward_epoch_id = [0u8; 8];
+        reward_epoch_id.copy_from_slice(&input[4 + 24..4 + 32]);
+
+        Some(SigningCall {
+            duty,
+            reward_epoch_id: u64::from_be_bytes(reward_epoch_id),
+        })
+    }
+}
+
+// A signing duty which is due, for the reward epoch `reward_epoch_id`
+#[derive(Debug, Clone)]
+pub struct DutyStatus {
+    pub duty: SigningDuty,
+    pub reward_epoch_id: u64,
+    pub deadline: u64,
+    pub done: bool,
+    // Blocks were skipped since its signing opened, so it may have been done unseen
+    pub unscanned: bool,
+}
+
+impl DutyStatus {
+    pub fn missed(&self, now: u64) -> bool {
+        !self.done && now >= self.deadline
+    }
+
+    // Not performed yet and the deadline is less than `warning` seconds away
+    pub fn deadline_approaching(&self, now: u64, warning: u64) -> bool {
+        !self.done && now < self.deadline && now + warning >= self.deadline
+    }
+}
+
+// The signing duties performed by the signing policy address for each reward epoch
+// Each duty is counted, so that a call which was reorged out can be taken back
+#[derive(Debug, Clone, Default)]
+pub struct SigningDutyTracker {
+    done: BTreeMap<u64, BTreeMap<SigningDuty, u32>>,
+}
+
+impl SigningDutyTracker {
+    pub fn record(&mut self, call: &SigningCall) {
+        *self
+            .done
+            .entry(call.reward_epoch_id)
+            .or_default()
+            .entry(call.duty)
+            .or_default() += 1;
+    }
+
+    // Take back a call recorded before
+    pub fn unrecord(&mut self, call: &SigningCall) {
+        let Some(duties) = self.done.get_mut(&call.reward_epoch_id) else {
+            return;
+        };
+        if let Some(count) = duties.get_mut(&call.duty) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                duties.remove(&call.duty);
+            }
+        }
+        if duties.is_empty() {
+            self.done.remove(&call.reward_epoch_id);
+        }
+    }
+
+    pub fn is_done(&self, reward_epoch_id: u64, duty: SigningDuty) -> bool {
+        self.done
+            .get(&reward_epoch_id)
+            .is_some_and(|duties| duties.contains_key(&duty))
+    }
+
+    pub fn done_for(&self, reward_epoch_id: u64) -> BTreeSet<SigningDuty> {
+        self.done
+            .get(&reward_epoch_id)
+            .map(|duties| duties.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // The last reward epoch each duty was performed for
+    pub fn last_reward_epochs(&self) -> BTreeMap<SigningDuty, u64> {
+        let mut last = BTreeMap::new();
+        for (reward_epoch_id, duties) in &self.done {
+            for duty in duties.keys() {
+                last.insert(*duty, *reward_epoch_id);
+            }
+        }
+
+        last
+    }
+
+    // Forget the reward epochs before `reward_epoch_id`
+    pub fn prune(&mut self, reward_epoch_id: u64) {
+        self.done = self.done.split_off(&reward_epoch_id);
+    }
+}
+
+// How long after its signing opens on chain each signing duty has to be performed, in seconds
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DutyDeadlines {
+    pub uptime_vote: u64,
+    pub rewards: u64,
+    pub signing_policy: u64,
+    // Warn this long before the deadline of a duty which wasn't performed yet
+    pub warning: u64,
+}
+
+impl DutyDeadlines {
+    pub fn deadline(&self, duty: SigningDuty) -> u64 {
+        match duty {
+            SigningDuty::NewSigningPolicy => self.signing_policy,
+            SigningDuty::UptimeVote => self.uptime_vote,
+            SigningDuty::Rewards => self.rewards,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calldata(signature: &str, reward_epoch_id: u64) -> Vec<u8> {
+        let mut input = keccak256(signature.as_bytes())[..4].to_vec();
+        input.extend([0u8; 24]);
+        input.extend(reward_epoch_id.to_be_bytes());
+        // The rest of the arguments don't matter
+        input.extend([0u8; 96]);
+        input
+    }
+
+    #[test]
+    fn decode_every_duty() {
+        for (signature, duty) in [
+            (
+                "signNewSigningPolicy(uint24,bytes32,(uint8,bytes32,bytes32))",
+                SigningDuty::NewSigningPolicy,
+            ),
+            (
+                "signUptimeVote(uint24,bytes32,(uint8,bytes32,bytes32))",
+                SigningDuty::UptimeVote,
+            ),
+            (
+                "signRewards(uint24,(uint256,uint256)[],bytes32,(uint8,bytes32,bytes32))",
+                SigningDuty::Rewards,
+            ),
+            (
+                "signRewards(uint24,uint64,bytes32,(uint8,bytes32,bytes32))",
+                SigningDuty::Rewards,
+            ),
+        ] {
+            assert_eq!(
+                SigningCall::decode(&calldata(signature, 250)),
+                Some(SigningCall {
+                    duty,
+                    reward_epoch_id: 250
+                }),
+                "{signature}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_ignores_other_calls() {
+        assert_eq!(
+            SigningCall::decode(&calldata("transfer(address,uint256)", 1)),
+            None
+        );
+        // Too short to hold the reward epoch ID
+        let input = calldata("signUptimeVote(uint24,bytes32,(uint8,bytes32,bytes32))", 1);
+        assert_eq!(SigningCall::decode(&input[..20]), None);
+        assert_eq!(SigningCall::decode(&[]), None);
+    }
+
+    #[test]
+    fn tracker_unrecord() {
+        let call = SigningCall {
+            duty: SigningDuty::Rewards,
+            reward_epoch_id: 250,
+        };
+        let mut tracker = SigningDutyTracker::default();
+        tracker.record(&call);
+        tracker.record(&call);
+        tracker.unrecord(&call);
+        assert!(tracker.is_done(250, SigningDuty::Rewards));
+
+        tracker.unrecord(&call);
+        assert!(!tracker.is_done(250, SigningDuty::Rewards));
+        assert!(tracker.last_reward_epochs().is_empty());
+    }
+
+    #[test]
+    fn duty_status_deadlines() {
+        let status = DutyStatus {
+            duty: SigningDuty::UptimeVote,
+            reward_epoch_id: 250,
+            deadline: 1000,
+            done: false,
+            unscanned: false,
+        };
+        assert!(!status.missed(999));
+        assert!(status.missed(1000));
+        assert!(status.deadline_approaching(900, 100));
+        assert!(!status.deadline_approaching(899, 100));
+        assert!(!status.deadline_approaching(1000, 100));
+
+        let done = DutyStatus {
+            done: true,
+            ..status
+        };
+        assert!(!done.missed(2000));
+        assert!(!done.deadline_approaching(950, 100));
+    }
+}
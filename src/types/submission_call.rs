@@ -0,0 +1,293 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use web3::signing::keccak256;
+
+// The functions of the Submission contract used by the FTSO data providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SubmissionFunction {
+    // Commit for the current voting round
+    Submit1,
+    // Reveal for the previous voting round
+    Submit2,
+    Submit3,
+    // Signatures for the previous voting round
+    SubmitSignatures,
+}
+
+impl SubmissionFunction {
+    pub const ALL: [SubmissionFunction; 4] = [
+        SubmissionFunction::Submit1,
+        SubmissionFunction::Submit2,
+        SubmissionFunction::Submit3,
+        SubmissionFunction::SubmitSignatures,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubmissionFunction::Submit1 => "submit1",
+            SubmissionFunction::Submit2 => "submit2",
+            SubmissionFunction::Submit3 => "submit3",
+            SubmissionFunction::SubmitSignatures => "submitSignatures",
+        }
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(format!("{}()", self.name()).as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    pub fn from_selector(selector: &[u8]) -> Option<SubmissionFunction> {
+        SubmissionFunction::ALL
+            .into_iter()
+            .find(|function| function.selector() == selector)
+    }
+}
+
+impl Display for SubmissionFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// The header of each protocol payload packed after the function selector:
+// protocol ID (1 byte), voting round ID (4 bytes), payload size (2 bytes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadHeader {
+    pub protocol_id: u8,
+    pub voting_round_id: u32,
+    pub size: u16,
+}
+
+const PAYLOAD_HEADER_SIZE: usize = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmissionCall {
+    pub function: SubmissionFunction,
+    pub payloads: Vec<PayloadHeader>,
+}
+
+impl SubmissionCall {
+    // Decode the calldata of a tx sent to the Submission contract
+    // Returns None if the selector isn't one of the Submission functions
+    pub fn decode(input: &[u8]) -> Option<SubmissionCall> {
+        if input.len() < 4 {
+            return None;
+        }
+        let function = SubmissionFunction::from_selector(&input[..4])?;
+
+        // A truncated payload ends the decoding but keeps the headers found before it
+        let mut payloads = vec![];
+        let mut rest = &input[4..];
+        while rest.len() >= PAYLOAD_HEADER_SIZE {
+            let header = PayloadHeader {
+                protocol_id: rest[0],
+                voting_round_id: u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]),
+                size: u16::from_be_bytes([rest[5], rest[6]]),
+            };
+            let end = PAYLOAD_HEADER_SIZE + header.size as usize;
+            if rest.len() < end {
+                break;
+            }
+            payloads.push(header);
+            rest = &rest[end..];
+        }
+
+        Some(SubmissionCall { function, payloads })
+    }
+
+    // The highest voting round ID in the payloads
+    pub fn voting_round_id(&self) -> Option<u32> {
+        self.payloads
+            .iter()
+            .map(|payload| payload.voting_round_id)
+            .max()
+    }
+}
+
+// How many voting rounds are remembered for each function, enough to take back
+// the calls of a reorg
+const VOTING_ROUNDS_KEPT: usize = 100;
+
+// Submission contract calls seen by the monitor since it started
+#[derive(Debug, Clone, Default)]
+pub struct SubmissionStats {
+    pub calls: BTreeMap<SubmissionFunction, u64>,
+    // The calls of each function counted by the highest voting round ID in their payloads
+    voting_rounds: BTreeMap<SubmissionFunction, BTreeMap<u32, u64>>,
+}
+
+impl SubmissionStats {
+    pub fn record(&mut self, call: &SubmissionCall) {
+        *self.calls.entry(call.function).or_default() += 1;
+
+        if let Some(voting_round_id) = call.voting_round_id() {
+            let voting_rounds = self.voting_rounds.entry(call.function).or_default();
+            *voting_rounds.entry(voting_round_id).or_default() += 1;
+            while voting_rounds.len() > VOTING_ROUNDS_KEPT {
+                voting_rounds.pop_first();
+            }
+        }
+    }
+
+    // Take back a call recorded before, the last voting round goes back to the
+    // one of the calls still counted
+    pub fn unrecord(&mut self, call: &SubmissionCall) {
+        if let Some(calls) = self.calls.get_mut(&call.function) {
+            *calls = calls.saturating_sub(1);
+        }
+
+        let (Some(voting_round_id), Some(voting_rounds)) = (
+            call.voting_round_id(),
+            self.voting_rounds.get_mut(&call.function),
+        ) else {
+            return;
+        };
+        if let Some(calls) = voting_rounds.get_mut(&voting_round_id) {
+            *calls -= 1;
+            if *calls == 0 {
+                voting_rounds.remove(&voting_round_id);
+            }
+        }
+    }
+
+    // The highest voting round ID found in the payloads of `function`
+    pub fn last_voting_round(&self, function: SubmissionFunction) -> Option<u32> {
+        self.voting_rounds
+            .get(&function)
+            .and_then(|voting_rounds| voting_rounds.keys().next_back())
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(protocol_id: u8, voting_round_id: u32, data: &[u8]) -> Vec<u8> {
+        let mut payload = vec![protocol_id];
+        payload.extend(voting_round_id.to_be_bytes());
+        payload.extend((data.len() as u16).to_be_bytes());
+        payload.extend(data);
+        payload
+    }
+
+    #[test]
+    fn selectors() {
+        assert_eq!(
+            SubmissionFunction::Submit1.selector(),
+            [0x6c, 0x53, 0x2f, 0xae]
+        );
+        assert_eq!(
+            SubmissionFunction::SubmitSignatures.selector(),
+            [0x57, 0xee, 0xd5, 0x80]
+        );
+        for function in SubmissionFunction::ALL {
+            assert_eq!(
+                SubmissionFunction::from_selector(&function.selector()),
+                Some(function)
+            );
+        }
+    }
+
+    #[test]
+    fn decode_payloads() {
+        let mut input = SubmissionFunction::Submit2.selector().to_vec();
+        input.extend(payload(100, 900_001, &[1, 2, 3]));
+        input.extend(payload(200, 900_002, &[]));
+
+        assert_eq!(
+            SubmissionCall::decode(&input),
+            Some(SubmissionCall {
+                function: SubmissionFunction::Submit2,
+                payloads: vec![
+                    PayloadHeader {
+                        protocol_id: 100,
+                        voting_round_id: 900_001,
+                        size: 3,
+                    },
+                    PayloadHeader {
+                        protocol_id: 200,
+                        voting_round_id: 900_002,
+                        size: 0,
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            SubmissionCall::decode(&input).unwrap().voting_round_id(),
+            Some(900_002)
+        );
+    }
+
+    #[test]
+    fn decode_truncated_input() {
+        // A call without payloads
+        let selector = SubmissionFunction::Submit1.selector();
+        let call = SubmissionCall::decode(&selector).unwrap();
+        assert!(call.payloads.is_empty());
+        assert_eq!(call.voting_round_id(), None);
+
+        // A payload shorter than its size keeps the ones before it
+        let mut input = selector.to_vec();
+        input.extend(payload(100, 7, &[1]));
+        input.extend(&payload(100, 8, &[1, 2, 3])[..9]);
+        let call = SubmissionCall::decode(&input).unwrap();
+        assert_eq!(call.payloads.len(), 1);
+        assert_eq!(call.voting_round_id(), Some(7));
+
+        // Not a Submission function or not even a selector
+        assert_eq!(SubmissionCall::decode(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(SubmissionCall::decode(&selector[..3]), None);
+    }
+
+    fn call(function: SubmissionFunction, voting_round_id: u32) -> SubmissionCall {
+        SubmissionCall {
+            function,
+            payloads: vec![PayloadHeader {
+                protocol_id: 100,
+                voting_round_id,
+                size: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn unrecord_moves_the_last_voting_round_back() {
+        let mut stats = SubmissionStats::default();
+        stats.record(&call(SubmissionFunction::Submit1, 10));
+        stats.record(&call(SubmissionFunction::Submit1, 11));
+        stats.record(&call(SubmissionFunction::Submit2, 10));
+        assert_eq!(
+            stats.last_voting_round(SubmissionFunction::Submit1),
+            Some(11)
+        );
+
+        stats.unrecord(&call(SubmissionFunction::Submit1, 11));
+        assert_eq!(stats.calls[&SubmissionFunction::Submit1], 1);
+        assert_eq!(
+            stats.last_voting_round(SubmissionFunction::Submit1),
+            Some(10)
+        );
+        assert_eq!(
+            stats.last_voting_round(SubmissionFunction::Submit2),
+            Some(10)
+        );
+
+        stats.unrecord(&call(SubmissionFunction::Submit1, 10));
+        assert_eq!(stats.calls[&SubmissionFunction::Submit1], 0);
+        assert_eq!(stats.last_voting_round(SubmissionFunction::Submit1), None);
+    }
+
+    #[test]
+    fn unrecord_keeps_a_voting_round_sent_twice() {
+        let mut stats = SubmissionStats::default();
+        stats.record(&call(SubmissionFunction::SubmitSignatures, 10));
+        stats.record(&call(SubmissionFunction::SubmitSignatures, 10));
+
+        stats.unrecord(&call(SubmissionFunction::SubmitSignatures, 10));
+        assert_eq!(
+            stats.last_voting_round(SubmissionFunction::SubmitSignatures),
+            Some(10)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+// Successful and reverted txs of each tracked address since the monitor started
+#[derive(Debug, Clone, Default)]
+pub struct TxOutcomes {
+    successful: BTreeMap<String, u64>,
+    reverted: BTreeMap<String, u64>,
+}
+
+impl TxOutcomes {
+    pub fn record(&mut self, address: &str, reverted: bool) {
+        let outcomes = if reverted {
+            &mut self.reverted
+        } else {
+            &mut self.successful
+        };
+        *outcomes.entry(address.to_lowercase()).or_default() += 1;
+    }
+
+    // Take back a tx recorded before
+    pub fn unrecord(&mut self, address: &str, reverted: bool) {
+        let outcomes = if reverted {
+            &mut self.reverted
+        } else {
+            &mut self.successful
+        };
+        if let Some(count) = outcomes.get_mut(&address.to_lowercase()) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    pub fn successful(&self, address: &str) -> u64 {
+        self.successful
+            .get(&address.to_lowercase())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn reverted(&self, address: &str) -> u64 {
+        self.reverted
+            .get(&address.to_lowercase())
+            .copied()
+            .unwrap_or_default()
+    }
+}
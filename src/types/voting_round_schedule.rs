@@ -0,0 +1,69 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+// FTSO voting rounds are back to back windows of `round_duration` seconds
+// starting at `first_round_start_ts`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct VotingRoundSchedule {
+    pub first_round_start_ts: u64,
+    pub round_duration: u64,
+}
+
+impl VotingRoundSchedule {
+    pub fn round_id(&self, timestamp: u64) -> u64 {
+        timestamp.saturating_sub(self.first_round_start_ts) / self.round_duration
+    }
+
+    pub fn round_start(&self, round_id: u64) -> u64 {
+        self.first_round_start_ts + round_id * self.round_duration
+    }
+
+    pub fn round_end(&self, round_id: u64) -> u64 {
+        self.round_start(round_id + 1)
+    }
+
+    // The rounds which started at or after `start_ts` and ended at or before `end_ts`
+    pub fn complete_rounds(&self, start_ts: u64, end_ts: u64) -> Range<u64> {
+        let mut first = self.round_id(start_ts);
+        if self.round_start(first) < start_ts {
+            first += 1;
+        }
+        let last = self.round_id(end_ts);
+
+        first..last.max(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEDULE: VotingRoundSchedule = VotingRoundSchedule {
+        first_round_start_ts: 1000,
+        round_duration: 90,
+    };
+
+    #[test]
+    fn round_bounds() {
+        assert_eq!(SCHEDULE.round_id(1000), 0);
+        assert_eq!(SCHEDULE.round_id(1089), 0);
+        assert_eq!(SCHEDULE.round_id(1090), 1);
+        // Before the first round
+        assert_eq!(SCHEDULE.round_id(0), 0);
+
+        assert_eq!(SCHEDULE.round_start(2), 1180);
+        assert_eq!(SCHEDULE.round_end(2), 1270);
+    }
+
+    #[test]
+    fn complete_rounds() {
+        // Exactly on the round boundaries
+        assert_eq!(SCHEDULE.complete_rounds(1000, 1270), 0..3);
+        // The partial rounds at both ends are left out
+        assert_eq!(SCHEDULE.complete_rounds(1001, 1269), 1..2);
+        // Shorter than a round
+        assert_eq!(SCHEDULE.complete_rounds(1010, 1080), 1..1);
+        assert!(SCHEDULE.complete_rounds(1100, 1000).is_empty());
+    }
+}